@@ -1,5 +1,48 @@
 use clap::{builder::styling, Arg, ArgAction, ArgGroup, ArgMatches, Command};
+use ribboncurls::Delimiters;
 
+fn delimiters_arg() -> Arg {
+    Arg::new("delimiters")
+        .help("The delimiters the template starts with, separated by a space. Eg: `--delimiters \"<% %>\"`")
+        .long("delimiters")
+        .action(ArgAction::Set)
+        .value_name("DELIMITERS")
+        .value_parser(str::parse::<Delimiters>)
+        .required(false)
+}
+
+fn partials_arg() -> Arg {
+    Arg::new("partials")
+        .short('p')
+        .value_name("FILE")
+        .help("A path to a file that maps partial names to partial file paths, read as JSON or TOML when it ends in .json or .toml and as YAML otherwise")
+        .long("partials")
+        .action(ArgAction::Append)
+        .value_name("FILE")
+        .required(false)
+}
+
+fn partial_file_arg() -> Arg {
+    Arg::new("partial-file")
+        .short('r')
+        .help("YAML data containing a \"partial\" property name and \"partial\" value (path to file to use as partial). Eg: `property_name: path/to/file.mustache`")
+        .long("partial-file")
+        .action(ArgAction::Append)
+        .value_name("YAML_STRING")
+        .required(false)
+}
+
+fn partial_delimiters_arg() -> Arg {
+    Arg::new("partial-delimiters")
+        .help("The delimiters the partials start with, when they differ from --delimiters")
+        .long("partial-delimiters")
+        .action(ArgAction::Set)
+        .value_name("DELIMITERS")
+        .value_parser(str::parse::<Delimiters>)
+        .required(false)
+}
+
+#[allow(clippy::too_many_lines)]
 fn build_cli() -> Command {
     Command::new(env!("CARGO_BIN_NAME"))
         .version(env!("CARGO_PKG_VERSION"))
@@ -13,7 +56,7 @@ fn build_cli() -> Command {
                         .help("The path to your mustache file, or read stdin with -")
                         .index(1)
                         .value_name("FILE")
-                        .required(true),
+                        .required_unless_present("print-context"),
                 )
                 .arg(
                     Arg::new("data")
@@ -27,31 +70,14 @@ fn build_cli() -> Command {
                 .arg(
                     Arg::new("data-file")
                         .short('f')
-                        .help("Path to your YAML data file")
+                        .help("Path to your data file, read as JSON or TOML when it ends in .json or .toml and as YAML otherwise")
                         .long("data-file")
                         .action(ArgAction::Append)
                         .value_name("FILE")
                         .required(false),
                 )
-                .arg(
-                    Arg::new("partials")
-                        .short('p')
-                        .value_name("FILE")
-                        .help("A path to a file that contains YAML partial data")
-                        .long("partials")
-                        .action(ArgAction::Append)
-                        .value_name("FILE")
-                        .required(false),
-                )
-                .arg(
-                    Arg::new("partial-file")
-                        .short('r')
-                        .help("YAML data containing a \"partial\" property name and \"partial\" value (path to file to use as partial). Eg: `property_name: path/to/file.mustache`")
-                        .long("partial-file")
-                        .action(ArgAction::Append)
-                        .value_name("YAML_STRING")
-                        .required(false),
-                )
+                .arg(partials_arg())
+                .arg(partial_file_arg())
                 .arg(
                     Arg::new("out")
                         .short('o')
@@ -62,11 +88,68 @@ fn build_cli() -> Command {
                         .value_name("OUTFILE")
                         .required(false),
                 )
+                .arg(
+                    Arg::new("merge-strategy")
+                        .help("How lists found in more than one data source are merged. Mappings are always merged key by key, with later data sources taking precedence")
+                        .long("merge-strategy")
+                        .action(ArgAction::Set)
+                        .value_name("STRATEGY")
+                        .value_parser(["replace", "append"])
+                        .default_value("replace")
+                        .required(false),
+                )
+                .arg(delimiters_arg().help("The delimiters the template and partials start with, separated by a space. Eg: `--delimiters \"<% %>\"`"))
+                .arg(partial_delimiters_arg())
+                .arg(
+                    Arg::new("print-context")
+                        .help("Print the merged data as YAML instead of rendering the template")
+                        .long("print-context")
+                        .action(ArgAction::SetTrue)
+                        .required(false),
+                )
                 .group(ArgGroup::new("required_flags")
                     .args(["data", "data-file"])
                     .required(true)
                     .multiple(true)),
         )
+        .subcommand(
+            Command::new("inspect")
+                .about("Lists the variables, sections and partials used by a template")
+                .arg(
+                    Arg::new("mustache-file-path")
+                        .help("The path to your mustache file, or read stdin with -")
+                        .index(1)
+                        .value_name("FILE")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("format")
+                        .help("Print one reference per line, or a JSON array of references")
+                        .long("format")
+                        .action(ArgAction::Set)
+                        .value_name("FORMAT")
+                        .value_parser(["text", "json"])
+                        .default_value("text")
+                        .required(false),
+                )
+                .arg(delimiters_arg()),
+        )
+        .subcommand(
+            Command::new("check")
+                .about("Checks that templates and partials parse, without rendering them")
+                .arg(
+                    Arg::new("mustache-file-paths")
+                        .help("The paths to your mustache files, or read stdin with -")
+                        .index(1)
+                        .value_name("FILES")
+                        .action(ArgAction::Append)
+                        .required_unless_present_any(["partials", "partial-file"]),
+                )
+                .arg(partials_arg())
+                .arg(partial_file_arg())
+                .arg(delimiters_arg().help("The delimiters the templates and partials start with, separated by a space. Eg: `--delimiters \"<% %>\"`"))
+                .arg(partial_delimiters_arg()),
+        )
 }
 
 pub fn get_matches() -> ArgMatches {
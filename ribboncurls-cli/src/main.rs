@@ -2,8 +2,13 @@ mod cli;
 mod operations;
 
 use crate::cli::get_matches;
-use crate::operations::render::render;
+use crate::operations::check::check;
+use crate::operations::inspect::{inspect, InspectFormat};
+use crate::operations::read_partial_paths;
+use crate::operations::render::{render, DataSource, MergeStrategy};
 use anyhow::{Context, Result};
+use clap::ArgMatches;
+use ribboncurls::Delimiters;
 use std::path::PathBuf;
 use std::process::exit;
 
@@ -18,44 +23,75 @@ fn main() -> Result<()> {
             sub_matches.get_one::<String>("out").map(PathBuf::from);
         let mustache_input = sub_matches
             .get_one::<String>("mustache-file-path")
-            .context("`mustache-file-path` is missing")?;
-        let data_files = sub_matches
-            .get_many::<String>("data-file")
-            .unwrap_or_default()
-            .map(String::as_str)
-            .collect::<Vec<&str>>();
-        let cli_data = sub_matches
-            .get_many::<String>("data")
-            .unwrap_or_default()
-            .map(String::as_str)
-            .collect::<Vec<&str>>();
-        let data = if cli_data.is_empty() {
-            None
-        } else {
-            Some(cli_data.join("\n"))
+            .map(String::as_str);
+        // Data sources are merged in the order they were given, whichever flag was used
+        let indexed_values = |flag| {
+            sub_matches.indices_of(flag).into_iter().flatten().zip(
+                sub_matches
+                    .get_many::<String>(flag)
+                    .into_iter()
+                    .flatten()
+                    .cloned(),
+            )
         };
-        let cli_partials_with_filepath = sub_matches
-            .get_many::<String>("partial-file")
-            .unwrap_or_default()
+        let mut data_sources: Vec<(usize, DataSource)> = indexed_values("data")
+            .map(|(index, data)| (index, DataSource::Inline(data)))
+            .chain(indexed_values("data-file").map(|(index, path)| (index, DataSource::File(path))))
+            .collect();
+        data_sources.sort_by_key(|(index, _)| *index);
+        let data_sources = data_sources.into_iter().map(|(_, source)| source).collect();
+        let merge_strategy = match sub_matches
+            .get_one::<String>("merge-strategy")
             .map(String::as_str)
-            .collect::<Vec<&str>>();
-        let cli_partials = sub_matches
-            .get_many::<String>("partials")
-            .unwrap_or_default()
-            .collect::<Vec<&String>>();
-        let partials_with_filepath = if cli_partials_with_filepath.is_empty() {
-            None
-        } else {
-            Some(cli_partials_with_filepath.join("\n"))
+        {
+            Some("append") => MergeStrategy::Append,
+            _ => MergeStrategy::Replace,
         };
+        let print_context = sub_matches.get_flag("print-context");
+        let (delimiters, partial_delimiters) = get_delimiter_args(sub_matches);
+        let (cli_partials, partials_with_filepath) = get_partial_args(sub_matches);
+        let partial_paths = read_partial_paths(&cli_partials, partials_with_filepath.as_deref())?;
 
         render(
             mustache_input,
-            data,
-            data_files,
-            cli_partials,
-            partials_with_filepath,
+            data_sources,
+            partial_paths,
             out_path_option,
+            merge_strategy,
+            print_context,
+            &delimiters,
+            &partial_delimiters,
+        )?;
+    } else if let Some(("inspect", sub_matches)) = cli_matches.subcommand() {
+        let mustache_input = sub_matches
+            .get_one::<String>("mustache-file-path")
+            .context("`mustache-file-path` is missing")?;
+        let format = match sub_matches.get_one::<String>("format").map(String::as_str) {
+            Some("json") => InspectFormat::Json,
+            _ => InspectFormat::Text,
+        };
+
+        inspect(
+            mustache_input,
+            format,
+            &get_delimiters(sub_matches, "delimiters"),
+        )?;
+    } else if let Some(("check", sub_matches)) = cli_matches.subcommand() {
+        let mustache_inputs = sub_matches
+            .get_many::<String>("mustache-file-paths")
+            .unwrap_or_default()
+            .map(String::as_str)
+            .collect::<Vec<&str>>();
+
+        let (cli_partials, partials_with_filepath) = get_partial_args(sub_matches);
+        let partial_paths = read_partial_paths(&cli_partials, partials_with_filepath.as_deref())?;
+        let (delimiters, partial_delimiters) = get_delimiter_args(sub_matches);
+
+        check(
+            &mustache_inputs,
+            &partial_paths,
+            &delimiters,
+            &partial_delimiters,
         )?;
     } else {
         println!("Basic usage: {BIN_NAME} render <mustache-file-path> <yaml-data-file-path>");
@@ -65,3 +101,42 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// Returns the `--partials` paths and the `--partial-file` strings joined into one YAML mapping
+fn get_partial_args(matches: &ArgMatches) -> (Vec<&String>, Option<String>) {
+    let cli_partials_with_filepath = matches
+        .get_many::<String>("partial-file")
+        .unwrap_or_default()
+        .map(String::as_str)
+        .collect::<Vec<&str>>();
+    let cli_partials = matches
+        .get_many::<String>("partials")
+        .unwrap_or_default()
+        .collect::<Vec<&String>>();
+    let partials_with_filepath = if cli_partials_with_filepath.is_empty() {
+        None
+    } else {
+        Some(cli_partials_with_filepath.join("\n"))
+    };
+
+    (cli_partials, partials_with_filepath)
+}
+
+/// Returns the delimiters for templates and for partials, which start with the template's
+/// delimiters unless they are given their own
+fn get_delimiter_args(matches: &ArgMatches) -> (Delimiters, Delimiters) {
+    let delimiters = get_delimiters(matches, "delimiters");
+    let partial_delimiters = matches
+        .get_one::<Delimiters>("partial-delimiters")
+        .cloned()
+        .unwrap_or_else(|| delimiters.clone());
+
+    (delimiters, partial_delimiters)
+}
+
+fn get_delimiters(matches: &ArgMatches, flag: &str) -> Delimiters {
+    matches
+        .get_one::<Delimiters>(flag)
+        .cloned()
+        .unwrap_or_default()
+}
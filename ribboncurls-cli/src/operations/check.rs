@@ -0,0 +1,75 @@
+use anyhow::{anyhow, Context, Result};
+use ribboncurls::Delimiters;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use super::{read_template, with_snippet};
+
+/// Parses every template and partial without rendering them, printing each error to stderr.
+/// Returns an error if any template or partial can't be read or parsed.
+pub fn check(
+    template_paths: &[&str],
+    partial_paths: &HashMap<String, PathBuf>,
+    delimiters: &Delimiters,
+    partial_delimiters: &Delimiters,
+) -> Result<()> {
+    let stderr = io::stderr();
+    let mut stderr_handle = stderr.lock();
+    let mut error_count = 0;
+
+    for template_path_str in template_paths {
+        let result = read_template(template_path_str).and_then(|template| {
+            ribboncurls::Template::compile_with_delimiters(&template, delimiters)
+                .map(|_| ())
+                .map_err(|err| with_snippet(err, &template, &HashMap::new()))
+        });
+
+        if let Err(err) = result {
+            error_count += 1;
+            writeln!(stderr_handle, "{template_path_str}: {err:#}\n")?;
+        }
+    }
+
+    let mut partial_paths: Vec<_> = partial_paths.iter().collect();
+    partial_paths.sort();
+
+    for (name, path) in &partial_paths {
+        let result = fs::read_to_string(path)
+            .with_context(|| format!("Unable to read partial file: {}", path.display()))
+            .and_then(|partial| check_partial(name, partial, partial_delimiters));
+
+        if let Err(err) = result {
+            error_count += 1;
+            writeln!(stderr_handle, "{}: {err:#}\n", path.display())?;
+        }
+    }
+
+    if error_count > 0 {
+        return Err(anyhow!(
+            "Found errors in {error_count} of {} templates",
+            template_paths.len() + partial_paths.len()
+        ));
+    }
+
+    println!("No errors found");
+
+    Ok(())
+}
+
+/// Parses `partial` the way it is parsed when it is rendered as the `name` partial, so that errors
+/// are reported in the partial
+fn check_partial(name: &str, partial: String, delimiters: &Delimiters) -> Result<()> {
+    let partials = HashMap::from([(name.to_string(), partial)]);
+
+    ribboncurls::Template::compile("")
+        .and_then(|template| {
+            template
+                .with_partial_loader(partials.clone())
+                .with_partial_delimiters(delimiters.clone())
+                .preload_partial(name)
+        })
+        .map(|_| ())
+        .map_err(|err| with_snippet(err, "", &partials))
+}
@@ -0,0 +1,53 @@
+use anyhow::Result;
+use ribboncurls::Delimiters;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use super::{read_template, with_snippet};
+
+/// How `inspect` prints the references
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InspectFormat {
+    /// One reference per line
+    #[default]
+    Text,
+    /// A JSON array of references
+    Json,
+}
+
+pub fn inspect(
+    template_path_str: &str,
+    format: InspectFormat,
+    delimiters: &Delimiters,
+) -> Result<()> {
+    let template = read_template(template_path_str)?;
+    let references = ribboncurls::Template::compile_with_delimiters(&template, delimiters)
+        .map_err(|err| with_snippet(err, &template, &HashMap::new()))?
+        .references();
+
+    let stdout = io::stdout();
+    let mut stdout_handle = stdout.lock();
+
+    match format {
+        InspectFormat::Text => {
+            for reference in references {
+                let location = &reference.location;
+                write!(
+                    stdout_handle,
+                    "{}:{} {} {}",
+                    location.line, location.column, reference.kind, reference.name
+                )?;
+                if !reference.path.is_empty() {
+                    write!(stdout_handle, " (in {})", reference.path.join(" > "))?;
+                }
+                writeln!(stdout_handle)?;
+            }
+        }
+        InspectFormat::Json => {
+            serde_json::to_writer_pretty(&mut stdout_handle, &references)?;
+            writeln!(stdout_handle)?;
+        }
+    }
+
+    Ok(())
+}
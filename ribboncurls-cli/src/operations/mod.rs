@@ -1 +1,107 @@
+pub mod check;
+pub mod inspect;
 pub mod render;
+
+use anyhow::{anyhow, Context, Result};
+use ribboncurls::DataFormat;
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Reads the template from stdin if `template_path_str` is `-`, otherwise from the file
+pub fn read_template(template_path_str: &str) -> Result<String> {
+    if template_path_str == "-" {
+        let mut input = String::new();
+        io::stdin().read_to_string(&mut input)?;
+
+        Ok(input)
+    } else {
+        let template_path = PathBuf::from(template_path_str);
+        ensure_file_exists(&template_path)?;
+
+        fs::read_to_string(&template_path).context(format!(
+            "Unable to read from file: {}",
+            &template_path.display()
+        ))
+    }
+}
+
+/// Combines the partial names and file paths from `--partials` files and `--partial-file` strings
+pub fn read_partial_paths(
+    partials_paths: &[&String],
+    partials_content_option: Option<&str>,
+) -> Result<HashMap<String, PathBuf>> {
+    let mut paths = HashMap::new();
+
+    for partial_path_str in partials_paths {
+        let content = fs::read_to_string(partial_path_str).context(format!(
+            "Unable to read partial, does it exist? \"{partial_path_str}\"",
+        ))?;
+        let format = data_format(Path::new(partial_path_str));
+        paths.extend(
+            parse_partial_paths(&content, format)
+                .with_context(|| format!("Unable to parse partials: {partial_path_str}"))?,
+        );
+    }
+
+    if let Some(partials_content) = partials_content_option {
+        paths.extend(parse_partial_paths(partials_content, DataFormat::Yaml)?);
+    }
+
+    Ok(paths)
+}
+
+/// partials: mapping of partial names to partial file paths in the given format
+fn parse_partial_paths(partials: &str, format: DataFormat) -> Result<HashMap<String, PathBuf>> {
+    let partials: Option<HashMap<String, PathBuf>> =
+        serde_yaml::from_value(format.parse(partials)?)?;
+
+    Ok(partials.unwrap_or_default())
+}
+
+/// Picks the format of a data or partials file from its extension, defaulting to YAML
+pub fn data_format(path: &Path) -> DataFormat {
+    match path
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(str::to_ascii_lowercase)
+        .as_deref()
+    {
+        Some("json") => DataFormat::Json,
+        Some("toml") => DataFormat::Toml,
+        _ => DataFormat::Yaml,
+    }
+}
+
+/// Adds the offending line of the template or partial to errors caused by a broken template
+pub fn with_snippet(
+    err: ribboncurls::RibboncurlsError,
+    template: &str,
+    partials: &HashMap<String, String>,
+) -> anyhow::Error {
+    let Some(location) = err.location() else {
+        return anyhow::Error::new(err);
+    };
+    let source = location.partial.as_ref().map_or(Some(template), |name| {
+        partials.get(name).map(String::as_str)
+    });
+    let Some(source) = source else {
+        return anyhow::Error::new(err);
+    };
+    let snippet = location.snippet(source);
+
+    anyhow!("{err}\n\n{snippet}")
+}
+
+fn ensure_file_exists(file_path: &Path) -> Result<()> {
+    if !file_path.is_file() {
+        return Err(anyhow!(format!(
+            "{} is not a valid file",
+            file_path.display()
+        )));
+    }
+
+    Ok(())
+}
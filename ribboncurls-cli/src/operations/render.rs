@@ -1,82 +1,95 @@
 use anyhow::{anyhow, Context, Result};
+use ribboncurls::{DataFormat, Delimiters};
+use serde_yaml::Value;
 use std::collections::HashMap;
-use std::fmt::Write as FmtWrite;
 use std::fs::{self, OpenOptions};
-use std::io::{self, Read, Write};
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 
-pub fn render(
-    template_path_str: &str,
-    data_option: Option<String>,
-    data_files: Vec<&str>,
-    partials_paths: Vec<&String>,
-    partials_content_option: Option<String>,
-    out_path_option: Option<PathBuf>,
-) -> Result<()> {
-    // Combine content from --partials and --partials-file and read_from_string paths
-    let partials = {
-        let mut partials = String::new();
-
-        for partial_path_str in partials_paths {
-            let tmp_data = fs::read_to_string(partial_path_str).context(format!(
-                "Unable to read partial, does it exist? \"{partial_path_str}\"",
-            ))?;
-            partials.push_str(&tmp_data);
-            partials.push('\n');
-        }
-
-        if let Some(partials_content) = partials_content_option {
-            partials.push_str(&partials_content);
-        }
-
-        load_partials(&partials)?
-    };
+use super::{data_format, read_template, with_snippet};
 
-    // Combine data from data_option and data_files
-    let data_string = {
-        let data_vec = {
-            let mut data_vec: Vec<String> = Vec::new();
-            for data_file in data_files {
-                let tmp_data = fs::read_to_string(data_file).context(format!(
-                    "Unable to read data-file, does it exist? \"{data_file}\"",
-                ))?;
-                data_vec.push(tmp_data);
-            }
+/// A source of data for the template, as given on the command line
+pub enum DataSource {
+    /// A YAML string from `--data`
+    Inline(String),
+    /// A path from `--data-file`
+    File(String),
+}
 
-            data_vec
-        };
+/// How sequences found in more than one data source are merged
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// The sequence from the later source replaces the earlier one
+    #[default]
+    Replace,
+    /// Items from the later source are added to the end of the earlier sequence
+    Append,
+}
 
-        let data_inline = data_option.unwrap_or_default();
-        if data_inline.is_empty() && data_vec.is_empty() {
+#[allow(clippy::too_many_arguments)]
+pub fn render(
+    template_path_option: Option<&str>,
+    data_sources: Vec<DataSource>,
+    partial_paths: HashMap<String, PathBuf>,
+    out_path_option: Option<PathBuf>,
+    merge_strategy: MergeStrategy,
+    print_context: bool,
+    delimiters: &Delimiters,
+    partial_delimiters: &Delimiters,
+) -> Result<()> {
+    let partials = load_partials(partial_paths)?;
+
+    // Merge the data sources in order, so later sources take precedence
+    let data = {
+        if data_sources
+            .iter()
+            .all(|source| matches!(source, DataSource::Inline(data) if data.is_empty()))
+        {
             return Err(anyhow!(
                 "No data has been provided or the provided data is empty"
             ));
         }
 
-        let data = format!("{}\n{}", data_inline, data_vec.join("\n"));
+        let mut data = Value::Null;
+        for source in data_sources {
+            let source_data = match source {
+                DataSource::Inline(data) => DataFormat::Yaml
+                    .parse(&data)
+                    .with_context(|| format!("Unable to parse data: {data}"))?,
+                DataSource::File(data_file) => {
+                    let content = fs::read_to_string(&data_file).context(format!(
+                        "Unable to read data-file, does it exist? \"{data_file}\"",
+                    ))?;
+
+                    data_format(Path::new(&data_file))
+                        .parse(&content)
+                        .with_context(|| format!("Unable to parse data-file: {data_file}"))?
+                }
+            };
+
+            // An empty source has nothing to merge
+            if !source_data.is_null() {
+                merge_data(&mut data, source_data, merge_strategy);
+            }
+        }
 
         data
     };
 
-    // Read template from stdin if value is `-` otherwise attemp to
-    // locate and read from system file
-    let template_result: Result<String> = if template_path_str == "-" {
-        let mut input = String::new();
-        io::stdin().read_to_string(&mut input)?;
-
-        Ok(input)
+    let output = if print_context {
+        serde_yaml::to_string(&data)?
     } else {
-        let template_path = PathBuf::from(template_path_str);
-        ensure_file_exists(&template_path)?;
-
-        fs::read_to_string(&template_path).context(format!(
-            "Unable to read from file: {}",
-            &template_path.display()
-        ))
+        let template_path_str = template_path_option.context("A template is needed to render")?;
+
+        render_template(
+            template_path_str,
+            &data,
+            &partials,
+            delimiters,
+            partial_delimiters,
+        )?
     };
 
-    let output = ribboncurls::render(&template_result?, &data_string, Some(&partials))?;
-
     if let Some(out_path) = out_path_option {
         write_to_file(&out_path, &output)?;
         println!("Rendered output to: {}", out_path.display());
@@ -90,15 +103,23 @@ pub fn render(
     Ok(())
 }
 
-fn ensure_file_exists(file_path: &Path) -> Result<()> {
-    if !file_path.is_file() {
-        return Err(anyhow!(format!(
-            "{} is not a valid file",
-            file_path.display()
-        )));
-    }
-
-    Ok(())
+fn render_template(
+    template_path_str: &str,
+    data: &Value,
+    partials: &HashMap<String, String>,
+    delimiters: &Delimiters,
+    partial_delimiters: &Delimiters,
+) -> Result<String> {
+    let template = read_template(template_path_str)?;
+
+    ribboncurls::Template::compile_with_delimiters(&template, delimiters)
+        .and_then(|compiled| {
+            compiled
+                .with_partial_loader(partials.clone())
+                .with_partial_delimiters(partial_delimiters.clone())
+                .render_with(data)
+        })
+        .map_err(|err| with_snippet(err, &template, partials))
 }
 
 pub fn write_to_file(path: &Path, contents: &str) -> Result<()> {
@@ -119,25 +140,34 @@ pub fn write_to_file(path: &Path, contents: &str) -> Result<()> {
     Ok(())
 }
 
-/// partials: String in yaml format
-fn load_partials(partials: &str) -> Result<String> {
-    let partials: HashMap<String, PathBuf> = serde_yaml::from_str(partials)?;
-
-    let partials_with_content_result: Result<Vec<(String, String)>> = partials
-        .iter()
-        .map(|(key, value)| {
-            fs::read_to_string(value)
-                .map_err(anyhow::Error::new)
-                .map(|content| (key.clone(), content))
-        })
-        .collect();
-
-    let partials_with_content = partials_with_content_result?;
-
-    let mut combined_content = String::new();
-    for (key, content) in partials_with_content {
-        FmtWrite::write_fmt(&mut combined_content, format_args!("{key}: {content}\n"))?;
+/// Deep merges `source` into `data`. Mappings are merged key by key and sequences are merged
+/// using `strategy`. Any other value in `source` replaces the one in `data`.
+fn merge_data(data: &mut Value, source: Value, strategy: MergeStrategy) {
+    match (data, source) {
+        (Value::Mapping(data), Value::Mapping(source)) => {
+            for (key, value) in source {
+                match data.get_mut(&key) {
+                    Some(existing) => merge_data(existing, value, strategy),
+                    None => {
+                        data.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Sequence(data), Value::Sequence(source)) if strategy == MergeStrategy::Append => {
+            data.extend(source);
+        }
+        (data, source) => *data = source,
     }
+}
 
-    Ok(combined_content.trim_end().to_string())
+fn load_partials(partials: HashMap<String, PathBuf>) -> Result<HashMap<String, String>> {
+    partials
+        .into_iter()
+        .map(|(name, path)| {
+            fs::read_to_string(&path)
+                .with_context(|| format!("Unable to read partial file: {}", path.display()))
+                .map(|content| (name, content))
+        })
+        .collect()
 }
@@ -0,0 +1,178 @@
+#![allow(
+    clippy::unwrap_used,
+    clippy::unnecessary_wraps,
+    clippy::missing_errors_doc,
+    clippy::missing_panics_doc,
+    clippy::needless_pass_by_value
+)]
+
+use anyhow::Result;
+use std::error::Error;
+use std::process::Command;
+
+const COMMAND_PATH: &str = env!("CARGO_BIN_EXE_ribboncurls");
+const SUBCOMMAND_PATH: &str = "check";
+
+#[test]
+fn test_operation_check_valid_templates() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let mustache_filepaths = [
+        "./tests/fixtures/variables.mustache",
+        "./tests/fixtures/partials.mustache",
+        "./tests/fixtures/header.mustache",
+    ];
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr, status) = run_command(
+        [COMMAND_PATH, SUBCOMMAND_PATH]
+            .iter()
+            .chain(mustache_filepaths.iter())
+            .map(ToString::to_string)
+            .collect(),
+    )
+    .unwrap();
+
+    // ------
+    // Assert
+    // ------
+    assert!(status, "check exited with an error");
+    assert_eq!(stdout, "No errors found\n");
+    assert!(stderr.is_empty(), "stderr is not empty: {stderr}");
+
+    Ok(())
+}
+
+#[test]
+fn test_operation_check_reports_every_error() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let mustache_filepaths = [
+        "./tests/fixtures/mismatched-section.mustache",
+        "./tests/fixtures/variables.mustache",
+        "./tests/fixtures/unclosed-section.mustache",
+        "./tests/fixtures/missing.mustache",
+    ];
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr, status) = run_command(
+        [COMMAND_PATH, SUBCOMMAND_PATH]
+            .iter()
+            .chain(mustache_filepaths.iter())
+            .map(ToString::to_string)
+            .collect(),
+    )
+    .unwrap();
+
+    // ------
+    // Assert
+    // ------
+    assert!(!status, "check exited successfully");
+    assert!(stdout.is_empty(), "stdout is not empty: {stdout}");
+    assert!(
+        stderr.contains("./tests/fixtures/mismatched-section.mustache: expected close tag for section \"name\" but found \"nmae\" at line 2, column 10"),
+        "stderr does not contain the expected output: {stderr}"
+    );
+    assert!(
+        stderr.contains("2 | {{#name}}{{/nmae}}"),
+        "stderr does not contain the expected output: {stderr}"
+    );
+    assert!(
+        stderr.contains("./tests/fixtures/unclosed-section.mustache: "),
+        "stderr does not contain the expected output: {stderr}"
+    );
+    assert!(
+        stderr.contains("1 | {{#colors}}"),
+        "stderr does not contain the expected output: {stderr}"
+    );
+    assert!(
+        stderr.contains("./tests/fixtures/missing.mustache: ./tests/fixtures/missing.mustache is not a valid file"),
+        "stderr does not contain the expected output: {stderr}"
+    );
+    assert!(
+        stderr.contains("Found errors in 3 of 4 templates"),
+        "stderr does not contain the expected output: {stderr}"
+    );
+    assert!(
+        !stderr.contains("variables.mustache"),
+        "stderr reports a valid template: {stderr}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_operation_check_partials() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let args = [
+        "./tests/fixtures/partials.mustache",
+        "--partials",
+        "./tests/fixtures/partials.toml",
+        "--partial-file",
+        "colors: ./tests/fixtures/unclosed-section.mustache",
+    ];
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr, status) = run_command(
+        [COMMAND_PATH, SUBCOMMAND_PATH]
+            .iter()
+            .chain(args.iter())
+            .map(ToString::to_string)
+            .collect(),
+    )
+    .unwrap();
+
+    // ------
+    // Assert
+    // ------
+    assert!(!status, "check exited successfully");
+    assert!(stdout.is_empty(), "stdout is not empty: {stdout}");
+    assert!(
+        stderr.contains("./tests/fixtures/unclosed-section.mustache: section \"colors\" is never closed, opened at line 1, column 1 in partial \"colors\""),
+        "stderr does not contain the expected output: {stderr}"
+    );
+    assert!(
+        stderr.contains("1 | {{#colors}}"),
+        "stderr does not contain the expected output: {stderr}"
+    );
+    assert!(
+        stderr.contains("Found errors in 1 of 3 templates"),
+        "stderr does not contain the expected output: {stderr}"
+    );
+    assert!(
+        !stderr.contains("header.mustache"),
+        "stderr reports a valid partial: {stderr}"
+    );
+
+    Ok(())
+}
+
+pub fn run_command(command_vec: Vec<String>) -> Result<(String, String, bool), Box<dyn Error>> {
+    let output = Command::new(COMMAND_PATH)
+        .args(&command_vec[1..])
+        .output()
+        .expect("Failed to execute command");
+
+    if !output.stderr.is_empty() {
+        println!(
+            "Init command stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok((
+        String::from_utf8(output.stdout)?,
+        String::from_utf8(output.stderr)?,
+        output.status.success(),
+    ))
+}
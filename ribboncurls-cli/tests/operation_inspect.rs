@@ -0,0 +1,158 @@
+#![allow(
+    clippy::unwrap_used,
+    clippy::unnecessary_wraps,
+    clippy::missing_errors_doc,
+    clippy::missing_panics_doc,
+    clippy::needless_pass_by_value
+)]
+
+use anyhow::Result;
+use std::error::Error;
+use std::process::Command;
+
+const COMMAND_PATH: &str = env!("CARGO_BIN_EXE_ribboncurls");
+const SUBCOMMAND_PATH: &str = "inspect";
+
+#[test]
+fn test_operation_inspect_text() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let mustache_filepath = String::from("./tests/fixtures/scheme.mustache");
+    let expected_output = "1:1 variable scheme.name
+1:20 variable scheme.author
+1:39 variable palette.base00
+1:58 variable palette.base01
+1:77 section variants
+1:90 variable . (in variants)
+";
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(vec![
+        COMMAND_PATH.to_string(),
+        SUBCOMMAND_PATH.to_string(),
+        mustache_filepath,
+    ])
+    .unwrap();
+
+    // ------
+    // Assert
+    // ------
+    assert_eq!(stdout, expected_output);
+    assert!(stderr.is_empty(), "stderr is not empty: {stderr}");
+
+    Ok(())
+}
+
+#[test]
+fn test_operation_inspect_delimiters() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let mustache_filepath = String::from("./tests/fixtures/delimiters.mustache");
+    let expected_output = "1:1 partial header
+2:29 variable version
+";
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(vec![
+        COMMAND_PATH.to_string(),
+        SUBCOMMAND_PATH.to_string(),
+        "--delimiters=<% %>".to_string(),
+        mustache_filepath,
+    ])
+    .unwrap();
+
+    // ------
+    // Assert
+    // ------
+    assert_eq!(stdout, expected_output);
+    assert!(stderr.is_empty(), "stderr is not empty: {stderr}");
+
+    Ok(())
+}
+
+#[test]
+fn test_operation_inspect_json() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let mustache_filepath = String::from("./tests/fixtures/partials.mustache");
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(vec![
+        COMMAND_PATH.to_string(),
+        SUBCOMMAND_PATH.to_string(),
+        "--format=json".to_string(),
+        mustache_filepath,
+    ])
+    .unwrap();
+    let references: serde_json::Value = serde_json::from_str(&stdout)?;
+
+    // ------
+    // Assert
+    // ------
+    assert!(stderr.is_empty(), "stderr is not empty: {stderr}");
+    assert_eq!(references[0]["kind"], "partial");
+    assert_eq!(references[0]["name"], "header");
+    assert_eq!(references[1]["kind"], "variable");
+    assert_eq!(references[1]["name"], "name");
+    assert_eq!(references[1]["location"]["line"], 2);
+    assert_eq!(references[1]["location"]["column"], 8);
+
+    Ok(())
+}
+
+#[test]
+fn test_operation_inspect_broken_template() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let mustache_filepath = String::from("./tests/fixtures/mismatched-section.mustache");
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(vec![
+        COMMAND_PATH.to_string(),
+        SUBCOMMAND_PATH.to_string(),
+        mustache_filepath,
+    ])
+    .unwrap();
+
+    // ------
+    // Assert
+    // ------
+    assert!(stdout.is_empty(), "stdout is not empty: {stdout}");
+    assert!(
+        stderr.contains("expected close tag for section \"name\" but found \"nmae\""),
+        "stderr does not contain the expected output: {stderr}"
+    );
+
+    Ok(())
+}
+
+pub fn run_command(command_vec: Vec<String>) -> Result<(String, String), Box<dyn Error>> {
+    let output = Command::new(COMMAND_PATH)
+        .args(&command_vec[1..])
+        .output()
+        .expect("Failed to execute command");
+
+    if !output.stderr.is_empty() {
+        println!(
+            "Init command stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok((
+        String::from_utf8(output.stdout)?,
+        String::from_utf8(output.stderr)?,
+    ))
+}
@@ -1,3 +1,11 @@
+#![allow(
+    clippy::unwrap_used,
+    clippy::unnecessary_wraps,
+    clippy::missing_errors_doc,
+    clippy::missing_panics_doc,
+    clippy::needless_pass_by_value
+)]
+
 use anyhow::Result;
 use std::error::Error;
 use std::fs;
@@ -297,6 +305,305 @@ fn test_operation_render_out() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_operation_render_partial_file() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let mustache_filepath = String::from("./tests/fixtures/partials.mustache");
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(vec![
+        COMMAND_PATH.to_string(),
+        SUBCOMMAND_PATH.to_string(),
+        "--data=name: World".to_string(),
+        "--partial-file=header: ./tests/fixtures/header.mustache".to_string(),
+        mustache_filepath,
+    ])
+    .unwrap();
+
+    // ------
+    // Assert
+    // ------
+    assert_eq!(stdout, "# World\n# ---\nHello, World!\n\n");
+    assert!(
+        stderr.is_empty(),
+        "stderr does not contain the expected output"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_operation_render_delimiters() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let mustache_filepath = String::from("./tests/fixtures/delimiters.mustache");
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(vec![
+        COMMAND_PATH.to_string(),
+        SUBCOMMAND_PATH.to_string(),
+        "--data={name: World, version: 1.0}".to_string(),
+        "--partial-file=header: ./tests/fixtures/header.mustache".to_string(),
+        "--delimiters=<% %>".to_string(),
+        "--partial-delimiters={{ }}".to_string(),
+        mustache_filepath,
+    ])
+    .unwrap();
+
+    // ------
+    // Assert
+    // ------
+    assert_eq!(
+        stdout,
+        "# World\n# ---\nimage: \"{{ .Values.image }}:1.0\"\n\n"
+    );
+    assert!(
+        stderr.is_empty(),
+        "stderr does not contain the expected output"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_operation_render_invalid_delimiters() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let mustache_filepath = String::from("./tests/fixtures/delimiters.mustache");
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(vec![
+        COMMAND_PATH.to_string(),
+        SUBCOMMAND_PATH.to_string(),
+        "--data=name: World".to_string(),
+        "--delimiters=<%".to_string(),
+        mustache_filepath,
+    ])
+    .unwrap();
+
+    // ------
+    // Assert
+    // ------
+    assert!(stdout.is_empty(), "stdout is not empty: {stdout}");
+    assert!(
+        stderr.contains("expected 2 delimiters but found 1"),
+        "stderr does not contain the expected output: {stderr}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_operation_render_json_and_toml_datafiles() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let mustache_filepath = String::from("./tests/fixtures/variables.mustache");
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(vec![
+        COMMAND_PATH.to_string(),
+        SUBCOMMAND_PATH.to_string(),
+        format!(
+            "--data-file={}",
+            PathBuf::from("./tests/fixtures/data-name.json").display()
+        ),
+        format!(
+            "--data-file={}",
+            PathBuf::from("./tests/fixtures/data-lastname.toml").display()
+        ),
+        mustache_filepath,
+    ])
+    .unwrap();
+
+    // ------
+    // Assert
+    // ------
+    assert!(
+        stdout.contains("Hello, Jessica Doe!"),
+        "stdout does not contain the expected output"
+    );
+    assert!(
+        stderr.is_empty(),
+        "stderr does not contain the expected output"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_operation_render_toml_partials() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let mustache_filepath = String::from("./tests/fixtures/partials.mustache");
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(vec![
+        COMMAND_PATH.to_string(),
+        SUBCOMMAND_PATH.to_string(),
+        "--data=name: World".to_string(),
+        "--partials=./tests/fixtures/partials.toml".to_string(),
+        mustache_filepath,
+    ])
+    .unwrap();
+
+    // ------
+    // Assert
+    // ------
+    assert_eq!(stdout, "# World\n# ---\nHello, World!\n\n");
+    assert!(
+        stderr.is_empty(),
+        "stderr does not contain the expected output"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_operation_render_deep_merges_data() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let mustache_filepath = String::from("./tests/fixtures/scheme.mustache");
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(vec![
+        COMMAND_PATH.to_string(),
+        SUBCOMMAND_PATH.to_string(),
+        "--data=scheme: { author: Ignored }".to_string(),
+        "--data-file=./tests/fixtures/scheme-base.yaml".to_string(),
+        "--data-file=./tests/fixtures/scheme-override.json".to_string(),
+        "--data=palette: { base00: '080808' }".to_string(),
+        mustache_filepath,
+    ])
+    .unwrap();
+
+    // ------
+    // Assert
+    // ------
+    assert_eq!(stdout, "Override by Tinted: 080808 383838 light \n\n");
+    assert!(
+        stderr.is_empty(),
+        "stderr does not contain the expected output"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_operation_render_append_merge_strategy() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let mustache_filepath = String::from("./tests/fixtures/scheme.mustache");
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(vec![
+        COMMAND_PATH.to_string(),
+        SUBCOMMAND_PATH.to_string(),
+        "--data-file=./tests/fixtures/scheme-base.yaml".to_string(),
+        "--data-file=./tests/fixtures/scheme-override.json".to_string(),
+        "--merge-strategy=append".to_string(),
+        mustache_filepath,
+    ])
+    .unwrap();
+
+    // ------
+    // Assert
+    // ------
+    assert_eq!(stdout, "Override by Tinted: 181818 383838 dark light \n\n");
+    assert!(
+        stderr.is_empty(),
+        "stderr does not contain the expected output"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_operation_render_print_context() -> Result<()> {
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(vec![
+        COMMAND_PATH.to_string(),
+        SUBCOMMAND_PATH.to_string(),
+        "--data-file=./tests/fixtures/scheme-base.yaml".to_string(),
+        "--data=scheme: { name: Inline }".to_string(),
+        "--print-context".to_string(),
+    ])
+    .unwrap();
+
+    // ------
+    // Assert
+    // ------
+    assert_eq!(
+        stdout,
+        "scheme:\n  name: Inline\n  author: Tinted\npalette:\n  base00: '181818'\n  base01: '282828'\nvariants:\n- dark\n\n"
+    );
+    assert!(
+        stderr.is_empty(),
+        "stderr does not contain the expected output"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_operation_render_template_error_snippet() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let mustache_filepath = String::from("./tests/fixtures/mismatched-section.mustache");
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(vec![
+        COMMAND_PATH.to_string(),
+        SUBCOMMAND_PATH.to_string(),
+        "--data=name: World".to_string(),
+        mustache_filepath,
+    ])
+    .unwrap();
+
+    // ------
+    // Assert
+    // ------
+    assert!(stdout.is_empty(), "stdout should be empty");
+    assert!(
+        stderr.contains(
+            "expected close tag for section \"name\" but found \"nmae\" at line 2, column 10"
+        ),
+        "stderr does not contain the expected output"
+    );
+    assert!(
+        stderr.contains("2 | {{#name}}{{/nmae}}\n  |          ^^^^^^^^^"),
+        "stderr does not contain the expected output"
+    );
+
+    Ok(())
+}
+
 pub fn run_command(command_vec: Vec<String>) -> Result<(String, String), Box<dyn Error>> {
     let output = Command::new(COMMAND_PATH)
         .args(&command_vec[1..])
@@ -18,6 +18,48 @@ pub struct Test {
     pub expected: String,
 }
 
+/// Optional spec files which are supported and turned into tests
+const OPTIONAL_SPECS: [&str; 3] = ["~dynamic-names.yml", "~inheritance.yml", "~lambdas.yml"];
+
+/// The lambdas spec only provides lambda implementations for other languages, so the Rust
+/// equivalents are defined here, keyed by test name
+fn get_lambda_setup(test_name: &str) -> Option<&'static str> {
+    let setup = match test_name {
+        "Interpolation" => r#"lambdas.interpolation("lambda", || "world".to_string());"#,
+        "Interpolation - Expansion" => {
+            r#"lambdas.interpolation("lambda", || "{{planet}}".to_string());"#
+        }
+        "Interpolation - Alternate Delimiters" => {
+            r#"lambdas.interpolation("lambda", || "|planet| => {{planet}}".to_string());"#
+        }
+        "Interpolation - Multiple Calls" => {
+            r#"let calls = std::sync::atomic::AtomicUsize::new(0);
+                lambdas.interpolation("lambda", move || {
+                    (calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1).to_string()
+                });"#
+        }
+        "Escaping" => r#"lambdas.interpolation("lambda", || ">".to_string());"#,
+        "Section" => {
+            r#"lambdas.section("lambda", |text| {
+                    if text == "{{x}}" { "yes" } else { "no" }.to_string()
+                });"#
+        }
+        "Section - Expansion" => {
+            r#"lambdas.section("lambda", |text| [text, "{{planet}}", text].concat());"#
+        }
+        "Section - Alternate Delimiters" => {
+            r#"lambdas.section("lambda", |text| [text, "{{planet}} => |planet|", text].concat());"#
+        }
+        "Section - Multiple Calls" => {
+            r#"lambdas.section("lambda", |text| ["__", text, "__"].concat());"#
+        }
+        "Inverted Section" => r#"lambdas.section("lambda", |_| String::new());"#,
+        _ => return None,
+    };
+
+    Some(setup)
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let out_dir = std::env::var("OUT_DIR")?;
     let destination = std::path::Path::new(&out_dir).join("from_specs.rs");
@@ -31,26 +73,54 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             .into_string()
             .expect("Unable to get filename");
         let filename = Path::new(&filename_str);
-        if !filename.extension().map_or(false, |ext| ext == "yml") || filename_str.starts_with('~')
+        if !filename.extension().map_or(false, |ext| ext == "yml")
+            || (filename_str.starts_with('~') && !OPTIONAL_SPECS.contains(&filename_str.as_str()))
         {
             continue;
         }
         let path = entry.path();
 
-        let mod_name = filename_str.replace(".yml", "").replace('~', "");
+        let mod_name = filename_str
+            .replace(".yml", "")
+            .replace('~', "")
+            .replace('-', "_");
         writeln!(output_file, "mod {mod_name} {{")?;
 
         let spec_file: SpecFile = serde_yaml::from_reader(std::fs::File::open(path)?)?;
+        let mut test_names: Vec<String> = Vec::new();
         for test in spec_file.tests {
+            let render_call = if filename_str == "~lambdas.yml" {
+                let Some(lambda_setup) = get_lambda_setup(&test.name) else {
+                    println!(
+                        "cargo:warning=Skipping lambda spec test without a Rust lambda: {}",
+                        test.name
+                    );
+                    continue;
+                };
+
+                format!(
+                    r"let mut lambdas = ribboncurls::Lambdas::new();
+                {lambda_setup}
+                let output = ribboncurls::render_with_lambdas(template, data, partials, &lambdas)?;"
+                )
+            } else {
+                "let output = ribboncurls::render(template, data, partials)?;".to_string()
+            };
             let mut name = test
                 .name
                 .to_lowercase()
-                .replace([' ', '(', ')', '-'], "_")
+                .replace([' ', '(', ')', '-', ',', '\''], "_")
                 .replace("___", "_")
                 .replace("__", "_");
             if name.ends_with('_') {
                 name.pop();
             }
+            // Some spec files reuse a test name, so number the repeats to keep them unique
+            let repeat_count = test_names.iter().filter(|n| **n == name).count();
+            test_names.push(name.clone());
+            if repeat_count > 0 {
+                name = format!("{name}_{}", repeat_count + 1);
+            }
 
             let desc = test.desc.lines().fold(String::new(), |mut output, b| {
                 let _ = write!(output, "\n            /// {b}");
@@ -66,18 +136,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             write!(
                 output_file,
-                r##"
+                r###"
 
             #[test]{desc}
             fn {name}() -> Result<(), Box<dyn std::error::Error>> {{
                 let template = r#"{template}"#;
-                let data = r#"{data}"#;
+                let data = r##"{data}"##;
                 let partials = {partials};
                 let expected = r#"{expected}"#;
-                let output = ribboncurls::render(template, data, partials)?;
+                {render_call}
                 assert_eq!(output, expected);
                 Ok(())
-            }}"##
+            }}"###
             )?;
         }
 
@@ -0,0 +1,291 @@
+//! The parsed structure of a template, for tools such as linters, formatters and editors.
+//!
+//! [`parse`] keeps the template as it was written: text nodes hold the exact source text and
+//! every tag knows its original text and [`Location`], where `offset..offset + len` is its byte
+//! span. Concatenating the text of every node and tag in order gives back the template.
+//!
+//! ```rust
+//! use ribboncurls::ast::{self, Node};
+//!
+//! let ast = ast::parse("{{#colors}}\n  {{name}}\n{{/colors}}\n")?;
+//!
+//! let Node::Section { name, open, close, children, .. } = &ast.nodes[0] else {
+//!     panic!("expected a section");
+//! };
+//! assert_eq!(name, "colors");
+//! assert_eq!(open.text, "{{#colors}}");
+//! assert!(open.standalone && close.standalone);
+//! assert_eq!(close.location.offset, 23);
+//! assert!(matches!(&children[1], Node::Variable { name, escaped: true, .. } if name == "name"));
+//! # Ok::<(), ribboncurls::RibboncurlsError>(())
+//! ```
+
+use crate::{
+    location::Location,
+    syntax_tree::{
+        get_close_is_standalone, get_is_line_end, get_is_line_start, get_is_standalone,
+        get_open_is_standalone,
+    },
+    token::{tokenize, Token},
+    Delimiters, Limits, RibboncurlsError, SyntaxCtx, TokenCtx,
+};
+
+/// A parsed template
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Ast {
+    /// The top level nodes in template order
+    pub nodes: Vec<Node>,
+}
+
+/// A tag as written in the template
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Tag {
+    /// The tag including its delimiters, such as `{{# colors }}`
+    pub text: String,
+    /// Where the tag is in the template
+    pub location: Location,
+    /// Whether the tag is alone on its line, in which case the whole line is left out of the
+    /// rendered output. Variable tags are never standalone.
+    pub standalone: bool,
+}
+
+/// A piece of a template
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Node {
+    /// Text outside of tags, exactly as written
+    Text { text: String, location: Location },
+    /// `{{name}}`, or `{{{name}}}` and `{{&name}}` when not `escaped`
+    Variable {
+        name: String,
+        escaped: bool,
+        tag: Tag,
+    },
+    /// `{{#name}}...{{/name}}`, or `{{^name}}...{{/name}}` when `inverted`
+    Section {
+        name: String,
+        inverted: bool,
+        children: Vec<Self>,
+        open: Tag,
+        close: Tag,
+    },
+    /// `{{>name}}`, or `{{>*name}}` when `dynamic`, where `name` is looked up in the data to get
+    /// the partial name
+    Partial {
+        name: String,
+        dynamic: bool,
+        tag: Tag,
+    },
+    /// `{{<name}}...{{/name}}`, the `name` partial with blocks overridden by `children`
+    Parent {
+        name: String,
+        children: Vec<Self>,
+        open: Tag,
+        close: Tag,
+    },
+    /// `{{$name}}...{{/name}}`, content that a parent can override
+    Block {
+        name: String,
+        children: Vec<Self>,
+        open: Tag,
+        close: Tag,
+    },
+    /// `{{! text}}`
+    Comment { text: String, tag: Tag },
+    /// `{{=left right=}}`, after which tags use the `left` and `right` delimiters
+    SetDelimiters {
+        left: String,
+        right: String,
+        tag: Tag,
+    },
+}
+
+/// The kinds of node that have children
+enum OpenKind {
+    Section { inverted: bool },
+    Parent,
+    Block,
+}
+
+/// A node with children waiting on its close tag
+struct OpenNode {
+    kind: OpenKind,
+    name: String,
+    open: Tag,
+    children: Vec<Node>,
+}
+
+/// Parses a template into its [`Ast`].
+///
+/// # Errors
+///
+/// Returns a [`RibboncurlsError`] if the template can't be tokenized, or its sections aren't
+/// balanced.
+pub fn parse(template: &str) -> Result<Ast, RibboncurlsError> {
+    parse_with_delimiters(template, &Delimiters::default())
+}
+
+/// Parses a template that starts with `delimiters` instead of `{{` and `}}` into its [`Ast`], like
+/// [`Template::compile_with_delimiters`](crate::Template::compile_with_delimiters).
+///
+/// ```rust
+/// use ribboncurls::ast::{self, Node};
+///
+/// let ast = ast::parse_with_delimiters("{{ .Values }}<% name %>", &"<% %>".parse()?)?;
+///
+/// assert!(matches!(&ast.nodes[0], Node::Text { text, .. } if text == "{{ .Values }}"));
+/// assert!(matches!(&ast.nodes[1], Node::Variable { tag, .. } if tag.text == "<% name %>"));
+/// # Ok::<(), ribboncurls::RibboncurlsError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns a [`RibboncurlsError`] if the template can't be tokenized, or its sections aren't
+/// balanced.
+pub fn parse_with_delimiters(
+    template: &str,
+    delimiters: &Delimiters,
+) -> Result<Ast, RibboncurlsError> {
+    let mut token_ctx = TokenCtx::new(delimiters, None, Limits::default().max_section_depth());
+    let tokens = tokenize(template, &mut token_ctx)?;
+    let syntax_ctx = SyntaxCtx { is_root: true };
+    let tag = |location: &Location, standalone: bool| Tag {
+        text: template
+            .get(location.offset..location.offset + location.len)
+            .unwrap_or_default()
+            .to_string(),
+        location: location.clone(),
+        standalone,
+    };
+    let mut nodes = Vec::new();
+    let mut open_nodes: Vec<OpenNode> = Vec::new();
+
+    for (index, token) in tokens.iter().enumerate() {
+        let node = match token {
+            Token::Text(text, location) => Node::Text {
+                text: text.clone(),
+                location: location.clone(),
+            },
+            Token::Variable(name, location) | Token::EscapedVariable(name, location) => {
+                // Only for the error, the node keeps the tag's content including any filters
+                #[cfg(feature = "filters")]
+                crate::syntax_tree::VariableTag::parse(name, location)?;
+
+                Node::Variable {
+                    name: name.clone(),
+                    escaped: matches!(token, Token::EscapedVariable(..)),
+                    tag: tag(location, false),
+                }
+            }
+            Token::Partial(name, location) | Token::DynamicPartial(name, location) => {
+                Node::Partial {
+                    name: name.clone(),
+                    dynamic: matches!(token, Token::DynamicPartial(..)),
+                    tag: tag(location, get_is_standalone(&tokens, index, &syntax_ctx)),
+                }
+            }
+            Token::Comment(text, location) => Node::Comment {
+                text: text.clone(),
+                tag: tag(location, get_is_standalone(&tokens, index, &syntax_ctx)),
+            },
+            Token::Delimiter(left, right, location) => Node::SetDelimiters {
+                left: left.clone(),
+                right: right.clone(),
+                tag: tag(location, get_is_standalone(&tokens, index, &syntax_ctx)),
+            },
+            Token::OpenSection(name, _, location) | Token::OpenInvertedSection(name, location) => {
+                open_nodes.push(OpenNode {
+                    kind: OpenKind::Section {
+                        inverted: matches!(token, Token::OpenInvertedSection(..)),
+                    },
+                    name: name.clone(),
+                    open: tag(location, get_open_is_standalone(&tokens, index)),
+                    children: Vec::new(),
+                });
+                continue;
+            }
+            Token::OpenParent(name, location) | Token::OpenBlock(name, location) => {
+                let standalone = get_is_line_start(&tokens, index, &syntax_ctx)
+                    && get_is_line_end(&tokens, index);
+                open_nodes.push(OpenNode {
+                    kind: if matches!(token, Token::OpenParent(..)) {
+                        OpenKind::Parent
+                    } else {
+                        OpenKind::Block
+                    },
+                    name: name.clone(),
+                    open: tag(location, standalone),
+                    children: Vec::new(),
+                });
+                continue;
+            }
+            Token::CloseSection(_, location) | Token::CloseParent(location) => {
+                // `tokenize` has already checked that every close tag has an open tag
+                let Some(open_node) = open_nodes.pop() else {
+                    continue;
+                };
+
+                close_node(open_node, tag(location, false), &tokens, index, &syntax_ctx)
+            }
+        };
+
+        match open_nodes.last_mut() {
+            Some(open_node) => open_node.children.push(node),
+            None => nodes.push(node),
+        }
+    }
+
+    Ok(Ast { nodes })
+}
+
+/// Makes the node for `open_node` now that its `close` tag at `index` has been found
+fn close_node(
+    open_node: OpenNode,
+    mut close: Tag,
+    tokens: &[Token],
+    index: usize,
+    ctx: &SyntaxCtx,
+) -> Node {
+    let OpenNode {
+        kind,
+        name,
+        open,
+        children,
+    } = open_node;
+
+    match kind {
+        OpenKind::Section { inverted } => {
+            close.standalone = get_close_is_standalone(tokens, index);
+
+            Node::Section {
+                name,
+                inverted,
+                children,
+                open,
+                close,
+            }
+        }
+        OpenKind::Parent | OpenKind::Block => {
+            close.standalone =
+                get_is_line_start(tokens, index, ctx) && get_is_line_end(tokens, index);
+
+            if matches!(kind, OpenKind::Parent) {
+                Node::Parent {
+                    name,
+                    children,
+                    open,
+                    close,
+                }
+            } else {
+                Node::Block {
+                    name,
+                    children,
+                    open,
+                    close,
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,327 @@
+//! Colors for theme templates, with the `colors` cargo feature.
+//!
+//! The feature adds filters to [`Filters::new`](crate::Filters::new) that read a hex color, such
+//! as `7cafc2`, `#7cafc2`, `0x7cafc2`, `#abc` or `7cafc280` with alpha, and format or change it:
+//!
+//! | Filter | `{{ base0D \| filter }}` with `base0D: 7cafc2` |
+//! |--------|------------------------------------------------|
+//! | `hex` | `7cafc2`, with two more digits when the color isn't opaque |
+//! | `rgb` | `124, 175, 194`, or one channel with `rgb:"r"`, `"g"` or `"b"` |
+//! | `rgba` | `124, 175, 194, 1` |
+//! | `dec` | `0.4863, 0.6863, 0.7608`, or one channel with `dec:"r"`, `"g"`, `"b"` or `"a"` |
+//! | `hsl` | `196, 36%, 62%`, or one part with `hsl:"h"`, `"s"` or `"l"` |
+//! | `lighten:10%` | `9fc4d2`, with the lightness raised by 10 percentage points |
+//! | `darken:0.1` | `599ab2`, with the lightness lowered by 10 percentage points |
+//! | `mix:"181818",25%` | `638998`, a quarter of the way to `181818`. The weight defaults to 50%. |
+//! | `alpha:50%` | `7cafc280`, with the alpha set to a half |
+//!
+//! Amounts are a fraction such as `0.1` or a percentage such as `10%`. The filters that change
+//! a color give hex, so they can be followed by a format, as in `{{ base0D | darken:10% | rgb }}`.
+//!
+//! ```rust
+//! let template = ribboncurls::Template::compile("rgba({{ base0D | alpha:0.5 | rgba }})")?;
+//! assert_eq!(template.render("base0D: '#7cafc2'")?, "rgba(124, 175, 194, 0.502)");
+//! # Ok::<(), ribboncurls::RibboncurlsError>(())
+//! ```
+
+use crate::{filter::expect_args, Filters};
+
+/// An sRGB color with an alpha channel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: u8,
+}
+
+impl Color {
+    /// An opaque color
+    #[must_use]
+    pub const fn rgb(red: u8, green: u8, blue: u8) -> Self {
+        Self::rgba(red, green, blue, u8::MAX)
+    }
+
+    #[must_use]
+    pub const fn rgba(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
+        Self {
+            red,
+            green,
+            blue,
+            alpha,
+        }
+    }
+
+    /// Parses a `rgb`, `rrggbb` or `rrggbbaa` hex color, which can start with `#` or `0x`
+    #[must_use]
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let hex = text
+            .strip_prefix('#')
+            .or_else(|| text.strip_prefix("0x"))
+            .or_else(|| text.strip_prefix("0X"))
+            .unwrap_or(text);
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        let channel = |index: usize, len: usize| {
+            let digits = &hex[index * len..(index + 1) * len];
+            u8::from_str_radix(&digits.repeat(3 - len), 16).ok()
+        };
+        match hex.len() {
+            3 => Some(Self::rgb(channel(0, 1)?, channel(1, 1)?, channel(2, 1)?)),
+            6 | 8 => Some(Self::rgba(
+                channel(0, 2)?,
+                channel(1, 2)?,
+                channel(2, 2)?,
+                if hex.len() == 8 {
+                    channel(3, 2)?
+                } else {
+                    u8::MAX
+                },
+            )),
+            _ => None,
+        }
+    }
+
+    /// Formats the color as lowercase `rrggbb` hex, or `rrggbbaa` when it isn't opaque
+    #[must_use]
+    pub fn to_hex(&self) -> String {
+        let hex = format!("{:02x}{:02x}{:02x}", self.red, self.green, self.blue);
+
+        if self.alpha == u8::MAX {
+            hex
+        } else {
+            format!("{hex}{:02x}", self.alpha)
+        }
+    }
+
+    /// The hue in degrees from 0 to 360, and the saturation and lightness from 0 to 1
+    #[must_use]
+    pub fn to_hsl(&self) -> (f64, f64, f64) {
+        let [red, green, blue] = [self.red, self.green, self.blue].map(to_fraction);
+        let max = red.max(green).max(blue);
+        let min = red.min(green).min(blue);
+        let lightness = (max + min) / 2.0;
+        let delta = max - min;
+
+        if delta == 0.0 {
+            return (0.0, 0.0, lightness);
+        }
+
+        let saturation = delta / (1.0 - 2.0f64.mul_add(lightness, -1.0).abs());
+        let hue = if (max - red).abs() < f64::EPSILON {
+            ((green - blue) / delta).rem_euclid(6.0)
+        } else if (max - green).abs() < f64::EPSILON {
+            (blue - red) / delta + 2.0
+        } else {
+            (red - green) / delta + 4.0
+        };
+
+        (hue * 60.0, saturation, lightness)
+    }
+
+    /// Makes a color from a hue in degrees, and a saturation and lightness from 0 to 1
+    #[must_use]
+    pub fn from_hsl(hue: f64, saturation: f64, lightness: f64, alpha: u8) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let lightness = lightness.clamp(0.0, 1.0);
+        let chroma = (1.0 - 2.0f64.mul_add(lightness, -1.0).abs()) * saturation;
+        let hue = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (hue.rem_euclid(2.0) - 1.0).abs());
+        let (red, green, blue) = match hue {
+            hue if hue < 1.0 => (chroma, x, 0.0),
+            hue if hue < 2.0 => (x, chroma, 0.0),
+            hue if hue < 3.0 => (0.0, chroma, x),
+            hue if hue < 4.0 => (0.0, x, chroma),
+            hue if hue < 5.0 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let min = lightness - chroma / 2.0;
+
+        Self::rgba(
+            from_fraction(red + min),
+            from_fraction(green + min),
+            from_fraction(blue + min),
+            alpha,
+        )
+    }
+
+    /// Raises the lightness by `amount`, from 0 to 1
+    #[must_use]
+    pub fn lighten(&self, amount: f64) -> Self {
+        let (hue, saturation, lightness) = self.to_hsl();
+
+        Self::from_hsl(hue, saturation, lightness + amount, self.alpha)
+    }
+
+    /// Lowers the lightness by `amount`, from 0 to 1
+    #[must_use]
+    pub fn darken(&self, amount: f64) -> Self {
+        self.lighten(-amount)
+    }
+
+    /// Mixes in `weight` of `other`, from 0 for this color to 1 for `other`
+    #[must_use]
+    pub fn mix(&self, other: &Self, weight: f64) -> Self {
+        let weight = weight.clamp(0.0, 1.0);
+        let mix = |from: u8, to: u8| {
+            from_fraction((to_fraction(to) - to_fraction(from)).mul_add(weight, to_fraction(from)))
+        };
+
+        Self::rgba(
+            mix(self.red, other.red),
+            mix(self.green, other.green),
+            mix(self.blue, other.blue),
+            mix(self.alpha, other.alpha),
+        )
+    }
+
+    /// Sets the alpha, from 0 for transparent to 1 for opaque
+    #[must_use]
+    pub fn with_alpha(self, alpha: f64) -> Self {
+        Self {
+            alpha: from_fraction(alpha),
+            ..self
+        }
+    }
+}
+
+fn to_fraction(channel: u8) -> f64 {
+    f64::from(channel) / 255.0
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn from_fraction(fraction: f64) -> u8 {
+    // Clamped to 0..=255 first, so the cast can't truncate
+    (fraction.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Formats a fraction with at most four decimal places, such as `0.4863` or `1`
+fn format_fraction(fraction: f64) -> String {
+    let formatted = format!("{fraction:.4}");
+
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+fn parse_color(value: &str) -> Result<Color, String> {
+    Color::parse(value).ok_or_else(|| format!("\"{value}\" is not a hex color"))
+}
+
+/// Parses a fraction such as `0.1`, or a percentage such as `10%`
+fn parse_amount(arg: &str) -> Result<f64, String> {
+    let (number, scale) = arg
+        .strip_suffix('%')
+        .map_or((arg, 1.0), |number| (number, 100.0));
+
+    number
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|amount| amount.is_finite())
+        .map(|amount| amount / scale)
+        .ok_or_else(|| format!("\"{arg}\" is not an amount"))
+}
+
+/// The optional channel argument of a format filter, from `channels`
+fn parse_channel(args: &[String], channels: &[&str]) -> Result<Option<usize>, String> {
+    match args {
+        [] => Ok(None),
+        [channel] => channels
+            .iter()
+            .position(|name| name == channel)
+            .map(Some)
+            .ok_or_else(|| format!("\"{channel}\" isn't one of {}", channels.join(", "))),
+        _ => Err(format!(
+            "expected at most 1 argument but found {}",
+            args.len()
+        )),
+    }
+}
+
+#[allow(clippy::too_many_lines)]
+pub fn add_filters(filters: &mut Filters) {
+    filters
+        .filter("hex", |value, args| {
+            expect_args(args, 0)?;
+            Ok(parse_color(value)?.to_hex())
+        })
+        .filter("rgb", |value, args| {
+            let color = parse_color(value)?;
+            let channels = [color.red, color.green, color.blue];
+
+            Ok(parse_channel(args, &["r", "g", "b"])?.map_or_else(
+                || channels.map(|channel| channel.to_string()).join(", "),
+                |index| channels[index].to_string(),
+            ))
+        })
+        .filter("rgba", |value, args| {
+            expect_args(args, 0)?;
+            let color = parse_color(value)?;
+
+            Ok(format!(
+                "{}, {}, {}, {}",
+                color.red,
+                color.green,
+                color.blue,
+                format_fraction(to_fraction(color.alpha))
+            ))
+        })
+        .filter("dec", |value, args| {
+            let color = parse_color(value)?;
+            let channels = [color.red, color.green, color.blue, color.alpha]
+                .map(|channel| format_fraction(to_fraction(channel)));
+
+            Ok(parse_channel(args, &["r", "g", "b", "a"])?
+                .map_or_else(|| channels[..3].join(", "), |index| channels[index].clone()))
+        })
+        .filter("hsl", |value, args| {
+            let (hue, saturation, lightness) = parse_color(value)?.to_hsl();
+            let [hue, saturation, lightness] =
+                [hue, saturation * 100.0, lightness * 100.0].map(f64::round);
+
+            Ok(match parse_channel(args, &["h", "s", "l"])? {
+                Some(0) => hue.to_string(),
+                Some(1) => saturation.to_string(),
+                Some(_) => lightness.to_string(),
+                None => format!("{hue}, {saturation}%, {lightness}%"),
+            })
+        })
+        .filter("lighten", |value, args| {
+            expect_args(args, 1)?;
+            Ok(parse_color(value)?
+                .lighten(parse_amount(&args[0])?)
+                .to_hex())
+        })
+        .filter("darken", |value, args| {
+            expect_args(args, 1)?;
+            Ok(parse_color(value)?.darken(parse_amount(&args[0])?).to_hex())
+        })
+        .filter("mix", |value, args| {
+            let weight = match args {
+                [_] => 0.5,
+                [_, weight] => parse_amount(weight)?,
+                _ => {
+                    return Err(format!(
+                        "expected 1 or 2 arguments but found {}",
+                        args.len()
+                    ))
+                }
+            };
+
+            Ok(parse_color(value)?
+                .mix(&parse_color(&args[0])?, weight)
+                .to_hex())
+        })
+        .filter("alpha", |value, args| {
+            expect_args(args, 1)?;
+            Ok(parse_color(value)?
+                .with_alpha(parse_amount(&args[0])?)
+                .to_hex())
+        });
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use serde_yaml::{Mapping, Value};
+
+use crate::RibboncurlsError;
+
+/// The format of data and partials given as strings. Whatever the format, data is rendered the
+/// same way, so a template works the same with YAML, JSON or TOML data.
+///
+/// ```rust
+/// let template = ribboncurls::Template::compile("{{#palette}}{{name}}: #{{hex}} {{/palette}}")?;
+/// let data = r#"
+/// [[palette]]
+/// name = "base00"
+/// hex = "181818"
+/// "#;
+///
+/// assert_eq!(
+///     template.render_as(data, ribboncurls::DataFormat::Toml)?,
+///     "base00: #181818 "
+/// );
+/// # Ok::<(), ribboncurls::RibboncurlsError>(())
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DataFormat {
+    #[default]
+    Yaml,
+    Json,
+    Toml,
+}
+
+impl DataFormat {
+    /// Parses `data` into the value templates are rendered against. TOML dates and times become
+    /// strings.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RibboncurlsError`] if `data` isn't valid in this format.
+    pub fn parse(self, data: &str) -> Result<Value, RibboncurlsError> {
+        match self {
+            Self::Yaml => Ok(serde_yaml::from_str(data)?),
+            Self::Json => Ok(serde_json::from_str(data)?),
+            Self::Toml => Ok(toml_to_value(toml::from_str(data)?)),
+        }
+    }
+
+    /// Parses a mapping of partial names to templates. An empty YAML document or JSON `null` has
+    /// no partials.
+    pub(crate) fn parse_partials(
+        self,
+        partials: &str,
+    ) -> Result<HashMap<String, String>, RibboncurlsError> {
+        let partials: Option<HashMap<String, String>> = match self {
+            Self::Yaml => serde_yaml::from_str(partials)?,
+            Self::Json => serde_json::from_str(partials)?,
+            Self::Toml => Some(toml::from_str(partials)?),
+        };
+
+        Ok(partials.unwrap_or_default())
+    }
+}
+
+fn toml_to_value(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(string) => Value::String(string),
+        toml::Value::Integer(integer) => Value::Number(integer.into()),
+        toml::Value::Float(float) => Value::Number(float.into()),
+        toml::Value::Boolean(boolean) => Value::Bool(boolean),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(array) => {
+            Value::Sequence(array.into_iter().map(toml_to_value).collect())
+        }
+        toml::Value::Table(table) => Value::Mapping(
+            table
+                .into_iter()
+                .map(|(key, value)| (Value::String(key), toml_to_value(value)))
+                .collect::<Mapping>(),
+        ),
+    }
+}
@@ -0,0 +1,99 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{RibboncurlsError, DEFAULT_LEFT_DELIMITER, DEFAULT_RIGHT_DELIMITER};
+
+/// The tag delimiters a template or partial starts with, `{{` and `}}` by default. Set delimiter
+/// tags such as `{{=<% %>=}}` still change them part way through.
+///
+/// ```rust
+/// use ribboncurls::Delimiters;
+///
+/// let delimiters: Delimiters = "<% %>".parse()?;
+///
+/// assert_eq!(delimiters, Delimiters::new("<%", "%>")?);
+/// assert_eq!(delimiters.left(), "<%");
+/// # Ok::<(), ribboncurls::RibboncurlsError>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Delimiters {
+    left: String,
+    right: String,
+}
+
+impl Default for Delimiters {
+    fn default() -> Self {
+        Self {
+            left: DEFAULT_LEFT_DELIMITER.to_string(),
+            right: DEFAULT_RIGHT_DELIMITER.to_string(),
+        }
+    }
+}
+
+impl Delimiters {
+    /// Creates delimiters from the strings that open and close tags.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RibboncurlsError::InvalidDelimiters`] if either delimiter is empty, or contains
+    /// whitespace or `=`, which couldn't be set with a set delimiter tag.
+    pub fn new(left: &str, right: &str) -> Result<Self, RibboncurlsError> {
+        for delimiter in [left, right] {
+            if let Some(message) = delimiter_problem(delimiter) {
+                return Err(RibboncurlsError::InvalidDelimiters {
+                    delimiters: format!("{left} {right}"),
+                    message,
+                });
+            }
+        }
+
+        Ok(Self {
+            left: left.to_string(),
+            right: right.to_string(),
+        })
+    }
+
+    #[must_use]
+    pub fn left(&self) -> &str {
+        &self.left
+    }
+
+    #[must_use]
+    pub fn right(&self) -> &str {
+        &self.right
+    }
+}
+
+/// Parses two delimiters separated by whitespace, like the inside of a set delimiter tag
+impl FromStr for Delimiters {
+    type Err = RibboncurlsError;
+
+    fn from_str(delimiters: &str) -> Result<Self, Self::Err> {
+        match delimiters.split_whitespace().collect::<Vec<_>>()[..] {
+            [left, right] => Self::new(left, right),
+            ref parts => Err(RibboncurlsError::InvalidDelimiters {
+                delimiters: delimiters.to_string(),
+                message: format!("expected 2 delimiters but found {}", parts.len()),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Delimiters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.left, self.right)
+    }
+}
+
+/// Describes why `delimiter` can't be used, or returns `None` if it can
+pub fn delimiter_problem(delimiter: &str) -> Option<String> {
+    if delimiter.is_empty() {
+        Some("delimiter is empty".to_string())
+    } else if delimiter.contains(char::is_whitespace) {
+        Some(format!("delimiter \"{delimiter}\" contains whitespace"))
+    } else if delimiter.contains('=') {
+        Some(format!("delimiter \"{delimiter}\" contains \"=\""))
+    } else {
+        None
+    }
+}
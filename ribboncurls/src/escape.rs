@@ -0,0 +1,122 @@
+use std::fmt;
+use std::fmt::Write;
+
+use crate::utils::escape_html;
+
+/// Escapes the values of `{{name}}` variable tags. Triple mustache (`{{{name}}}`) and ampersand
+/// (`{{&name}}`) tags are never escaped.
+///
+/// The escaper is chosen with [`Template::with_escaper`](crate::Template::with_escaper) and is
+/// [`HtmlEscaper`] by default, as the Mustache spec requires. Any `Fn(&str) -> String` closure is
+/// an escaper too. The output of variable lambdas is escaped a piece at a time as it renders, so an
+/// escaper should give the same result for a value escaped whole or in parts.
+///
+/// ```rust
+/// let template = ribboncurls::Template::compile("let g:name = '{{name}}'")?
+///     .with_escaper(|text: &str| text.replace('\'', "''"));
+///
+/// assert_eq!(template.render("name: Tinted's")?, "let g:name = 'Tinted''s'");
+/// # Ok::<(), ribboncurls::RibboncurlsError>(())
+/// ```
+pub trait Escaper: Send + Sync {
+    fn escape(&self, text: &str) -> String;
+}
+
+impl fmt::Debug for dyn Escaper + '_ {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Escaper")
+    }
+}
+
+impl<F> Escaper for F
+where
+    F: Fn(&str) -> String + Send + Sync,
+{
+    fn escape(&self, text: &str) -> String {
+        self(text)
+    }
+}
+
+/// Escapes `&`, `<`, `>`, `"` and `'` as HTML character references
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HtmlEscaper;
+
+impl Escaper for HtmlEscaper {
+    fn escape(&self, text: &str) -> String {
+        escape_html(text)
+    }
+}
+
+/// Leaves values as they are, for output where no characters are special
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoEscaper;
+
+impl Escaper for NoEscaper {
+    fn escape(&self, text: &str) -> String {
+        text.to_string()
+    }
+}
+
+/// Escapes values for use inside a double quoted JSON string, such as `"{{name}}"`. The quotes
+/// themselves are left to the template.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JsonStringEscaper;
+
+impl Escaper for JsonStringEscaper {
+    fn escape(&self, text: &str) -> String {
+        let mut output = String::with_capacity(text.len());
+
+        for c in text.chars() {
+            match c {
+                '"' => output.push_str("\\\""),
+                '\\' => output.push_str("\\\\"),
+                '\n' => output.push_str("\\n"),
+                '\r' => output.push_str("\\r"),
+                '\t' => output.push_str("\\t"),
+                '\u{8}' => output.push_str("\\b"),
+                '\u{c}' => output.push_str("\\f"),
+                c if u32::from(c) < 0x20 => {
+                    // Writing to a String can't fail
+                    let _ = write!(output, "\\u{:04x}", u32::from(c));
+                }
+                c => output.push(c),
+            }
+        }
+
+        output
+    }
+}
+
+/// Escapes values for use inside a single quoted shell string, such as `'{{name}}'`, by closing
+/// the string around each `'`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ShellSingleQuoteEscaper;
+
+impl Escaper for ShellSingleQuoteEscaper {
+    fn escape(&self, text: &str) -> String {
+        text.replace('\'', r"'\''")
+    }
+}
+
+/// Escapes `&`, `<`, `>`, `"` and `'` as the predefined XML entities
+#[derive(Clone, Copy, Debug, Default)]
+pub struct XmlEscaper;
+
+impl Escaper for XmlEscaper {
+    fn escape(&self, text: &str) -> String {
+        let mut output = String::with_capacity(text.len());
+
+        for c in text.chars() {
+            match c {
+                '&' => output.push_str("&amp;"),
+                '<' => output.push_str("&lt;"),
+                '>' => output.push_str("&gt;"),
+                '"' => output.push_str("&quot;"),
+                '\'' => output.push_str("&apos;"),
+                c => output.push(c),
+            }
+        }
+
+        output
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::{location::Location, RibboncurlsError};
+
+type FilterFn = dyn Fn(&str, &[String]) -> Result<String, String> + Send + Sync;
+
+/// Named functions that change the value of a variable tag, used as
+/// `{{ name | upper | default:"none" }}`.
+///
+/// Filters are applied left to right to the value as a string, before it is escaped. Arguments
+/// follow a `:` and are separated by commas, either double quoted (with `\"` and `\\` escapes) or
+/// bare, as in `replace:"-"," "` or `default:0`.
+///
+/// [`Filters::new`] has the standard filters:
+///
+/// - `upper` and `lower` change the case of the value
+/// - `capitalize` makes the first character uppercase
+/// - `trim` removes leading and trailing whitespace
+/// - `replace:"from","to"` replaces every `from` with `to`
+/// - `default:"value"` uses `value` when the value is empty or missing. A missing value with a
+///   `default` filter isn't an error in strict mode.
+///
+/// With the `colors` feature, it also has the [color filters](crate::color).
+///
+/// ```rust
+/// let mut filters = ribboncurls::Filters::new();
+/// filters.filter("reverse", |value, _args| Ok(value.chars().rev().collect()));
+///
+/// let template = ribboncurls::Template::compile("{{ name | reverse | upper }} {{ author | default:\"unknown\" }}")?
+///     .with_filters(filters);
+/// assert_eq!(template.render("name: tinted")?, "DETNIT unknown");
+/// # Ok::<(), ribboncurls::RibboncurlsError>(())
+/// ```
+pub struct Filters {
+    filters: HashMap<String, Box<FilterFn>>,
+}
+
+impl Filters {
+    /// Creates the standard filters
+    #[must_use]
+    pub fn new() -> Self {
+        let mut filters = Self {
+            filters: HashMap::new(),
+        };
+        filters
+            .filter("upper", |value, args| {
+                expect_args(args, 0)?;
+                Ok(value.to_uppercase())
+            })
+            .filter("lower", |value, args| {
+                expect_args(args, 0)?;
+                Ok(value.to_lowercase())
+            })
+            .filter("capitalize", |value, args| {
+                expect_args(args, 0)?;
+                let mut chars = value.chars();
+                Ok(chars.next().map_or_else(String::new, |first| {
+                    first.to_uppercase().chain(chars).collect()
+                }))
+            })
+            .filter("trim", |value, args| {
+                expect_args(args, 0)?;
+                Ok(value.trim().to_string())
+            })
+            .filter("replace", |value, args| {
+                expect_args(args, 2)?;
+                Ok(value.replace(&args[0], &args[1]))
+            })
+            .filter(DEFAULT_FILTER, |value, args| {
+                expect_args(args, 1)?;
+                Ok(if value.is_empty() {
+                    args[0].clone()
+                } else {
+                    value.to_string()
+                })
+            });
+        #[cfg(feature = "colors")]
+        crate::color::add_filters(&mut filters);
+
+        filters
+    }
+
+    /// Registers a filter, replacing any filter with the same name. The filter receives the
+    /// value and the arguments from the tag, and returns the new value or an error message.
+    pub fn filter<F>(&mut self, name: &str, filter: F) -> &mut Self
+    where
+        F: Fn(&str, &[String]) -> Result<String, String> + Send + Sync + 'static,
+    {
+        self.filters.insert(name.to_string(), Box::new(filter));
+        self
+    }
+
+    /// Applies `calls` to `value` in order
+    pub(crate) fn apply(
+        &self,
+        value: String,
+        calls: &[FilterCall],
+        location: &Location,
+    ) -> Result<String, RibboncurlsError> {
+        calls.iter().try_fold(value, |value, call| {
+            let filter =
+                self.filters
+                    .get(&call.name)
+                    .ok_or_else(|| RibboncurlsError::UnknownFilter {
+                        name: call.name.clone(),
+                        location: location.clone(),
+                    })?;
+
+            filter(&value, &call.args).map_err(|message| RibboncurlsError::Filter {
+                name: call.name.clone(),
+                message,
+                location: location.clone(),
+            })
+        })
+    }
+}
+
+impl Default for Filters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Filters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.filters.keys()).finish()
+    }
+}
+
+const DEFAULT_FILTER: &str = "default";
+
+pub fn expect_args(args: &[String], count: usize) -> Result<(), String> {
+    if args.len() == count {
+        Ok(())
+    } else {
+        Err(format!(
+            "expected {count} arguments but found {}",
+            args.len()
+        ))
+    }
+}
+
+/// A filter in a variable tag, such as `replace:"-"," "`
+#[derive(Clone, Debug)]
+pub struct FilterCall {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+impl FilterCall {
+    pub fn is_default(&self) -> bool {
+        self.name == DEFAULT_FILTER
+    }
+}
+
+/// Splits the content of a variable tag into the variable name and its filters
+pub fn parse(content: &str) -> Result<(&str, Vec<FilterCall>), String> {
+    let mut segments = split_unquoted(content, '|')?.into_iter();
+    let name = segments.next().unwrap_or_default().trim();
+    let calls = segments
+        .map(parse_filter_call)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if name.is_empty() && !calls.is_empty() {
+        return Err("missing variable name".to_string());
+    }
+
+    Ok((name, calls))
+}
+
+/// Splits `text` on every `separator` that isn't inside double quotes
+fn split_unquoted(text: &str, separator: char) -> Result<Vec<&str>, String> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut is_quoted = false;
+    let mut is_escaped = false;
+
+    for (index, c) in text.char_indices() {
+        match c {
+            _ if is_escaped => is_escaped = false,
+            '\\' if is_quoted => is_escaped = true,
+            '"' => is_quoted = !is_quoted,
+            c if c == separator && !is_quoted => {
+                segments.push(&text[start..index]);
+                start = index + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+
+    if is_quoted {
+        return Err("unclosed quote".to_string());
+    }
+    segments.push(&text[start..]);
+
+    Ok(segments)
+}
+
+/// Parses `name` or `name:arg,arg`
+fn parse_filter_call(text: &str) -> Result<FilterCall, String> {
+    let (name, args) = match text.split_once(':') {
+        Some((name, args)) => (name.trim(), parse_args(args)?),
+        None => (text.trim(), Vec::new()),
+    };
+
+    if name.is_empty() {
+        return Err("missing filter name".to_string());
+    }
+    if name.contains(|c: char| c.is_whitespace() || c == '"') {
+        return Err(format!("bad filter name \"{name}\""));
+    }
+
+    Ok(FilterCall {
+        name: name.to_string(),
+        args,
+    })
+}
+
+/// Parses comma separated arguments, each either double quoted or bare
+fn parse_args(text: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    loop {
+        skip_whitespace(&mut chars);
+        if chars.next_if_eq(&'"').is_some() {
+            args.push(parse_quoted_arg(&mut chars)?);
+        } else {
+            let mut arg = String::new();
+            while let Some(c) = chars.next_if(|c| *c != ',') {
+                if c == '"' {
+                    return Err("quote inside a bare argument".to_string());
+                }
+                arg.push(c);
+            }
+
+            let arg = arg.trim_end();
+            if arg.is_empty() {
+                return Err("missing argument".to_string());
+            }
+            args.push(arg.to_string());
+        }
+
+        skip_whitespace(&mut chars);
+        match chars.next() {
+            Some(',') => {}
+            Some(c) => return Err(format!("unexpected \"{c}\" after argument")),
+            None => return Ok(args),
+        }
+    }
+}
+
+/// Parses the rest of a double quoted argument, after the opening quote
+fn parse_quoted_arg(chars: &mut Peekable<Chars>) -> Result<String, String> {
+    let mut arg = String::new();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Ok(arg),
+            '\\' => match chars.next() {
+                Some(c @ ('"' | '\\')) => arg.push(c),
+                Some(c) => {
+                    arg.push('\\');
+                    arg.push(c);
+                }
+                None => break,
+            },
+            c => arg.push(c),
+        }
+    }
+
+    Err("unclosed quote".to_string())
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
@@ -0,0 +1,271 @@
+use std::fmt;
+
+use serde_yaml::{value::TaggedValue, Number, Value};
+
+/// Turns the values of variable tags into text, before they are escaped.
+///
+/// The formatter is chosen with
+/// [`Template::with_formatter`](crate::Template::with_formatter) and is [`MustacheFormatter`] by
+/// default. [`ConfigFormatter`] renders every kind of value, and any `Fn(&Value) -> String`
+/// closure is a formatter too.
+///
+/// ```rust
+/// use ribboncurls::{MustacheFormatter, ValueFormatter};
+/// use serde_yaml::Value;
+///
+/// let template = ribboncurls::Template::compile("enabled = {{enabled}}")?
+///     .with_formatter(|value: &Value| match value {
+///         Value::Bool(true) => "yes".to_string(),
+///         Value::Bool(false) => "no".to_string(),
+///         value => MustacheFormatter.format(value),
+///     });
+///
+/// assert_eq!(template.render("enabled: false")?, "enabled = no");
+/// # Ok::<(), ribboncurls::RibboncurlsError>(())
+/// ```
+pub trait ValueFormatter: Send + Sync {
+    fn format(&self, value: &Value) -> String;
+}
+
+impl fmt::Debug for dyn ValueFormatter + '_ {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ValueFormatter")
+    }
+}
+
+impl<F> ValueFormatter for F
+where
+    F: Fn(&Value) -> String + Send + Sync,
+{
+    fn format(&self, value: &Value) -> String {
+        self(value)
+    }
+}
+
+/// Formats values the way Mustache implementations usually do: strings and numbers as they are,
+/// `true` as `true`, and `false`, null, sequences, mappings and tagged values as nothing
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MustacheFormatter;
+
+impl ValueFormatter for MustacheFormatter {
+    fn format(&self, value: &Value) -> String {
+        match value {
+            Value::Number(number) => number.to_string(),
+            Value::String(string) => string.clone(),
+            Value::Bool(true) => true.to_string(),
+            _ => String::new(),
+        }
+    }
+}
+
+/// How [`ConfigFormatter`] renders mappings
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MappingFormat {
+    /// Nothing, as Mustache does
+    Empty,
+    /// Inline JSON, such as `{"name":"Tinted","dark":true}`
+    #[default]
+    Json,
+    /// Inline YAML, such as `{name: Tinted, dark: true}`
+    Yaml,
+}
+
+/// Formats every kind of value, for output such as config files where `false` or a list should
+/// show up rather than render nothing.
+///
+/// By default booleans are `true` and `false`, null is nothing, numbers are as written, sequence
+/// items are joined with `, ` and mappings are inline JSON. Tagged values are formatted as the
+/// value they tag.
+///
+/// ```rust
+/// use ribboncurls::{ConfigFormatter, MappingFormat};
+///
+/// let template = ribboncurls::Template::compile("{{dark}} {{opacity}} [{{fonts}}] {{size}}")?
+///     .with_formatter(
+///         ConfigFormatter::new()
+///             .float_precision(2)
+///             .separator(" | ")
+///             .mappings(MappingFormat::Yaml),
+///     );
+///
+/// assert_eq!(
+///     template.render("{dark: false, opacity: 0.9, fonts: [Hack, Fira Code], size: {w: 80, h: 24}}")?,
+///     "false 0.90 [Hack | Fira Code] {w: 80, h: 24}"
+/// );
+/// # Ok::<(), ribboncurls::RibboncurlsError>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct ConfigFormatter {
+    true_text: String,
+    false_text: String,
+    null_text: String,
+    float_precision: Option<usize>,
+    float_exponent: bool,
+    separator: String,
+    mappings: MappingFormat,
+}
+
+impl Default for ConfigFormatter {
+    fn default() -> Self {
+        Self {
+            true_text: true.to_string(),
+            false_text: false.to_string(),
+            null_text: String::new(),
+            float_precision: None,
+            float_exponent: false,
+            separator: ", ".to_string(),
+            mappings: MappingFormat::default(),
+        }
+    }
+}
+
+impl ConfigFormatter {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the text for `true` and `false`
+    #[must_use]
+    pub fn booleans(mut self, true_text: &str, false_text: &str) -> Self {
+        self.true_text = true_text.to_string();
+        self.false_text = false_text.to_string();
+
+        self
+    }
+
+    /// Sets the text for null, which is nothing by default
+    #[must_use]
+    pub fn null(mut self, text: &str) -> Self {
+        self.null_text = text.to_string();
+
+        self
+    }
+
+    /// Formats floats with exactly `precision` decimal places. Integers are left as they are.
+    #[must_use]
+    pub const fn float_precision(mut self, precision: usize) -> Self {
+        self.float_precision = Some(precision);
+
+        self
+    }
+
+    /// Formats floats in scientific notation, such as `1.5e3`
+    #[must_use]
+    pub const fn float_exponent(mut self, exponent: bool) -> Self {
+        self.float_exponent = exponent;
+
+        self
+    }
+
+    /// Sets the text between sequence items
+    #[must_use]
+    pub fn separator(mut self, separator: &str) -> Self {
+        self.separator = separator.to_string();
+
+        self
+    }
+
+    /// Sets how mappings are formatted
+    #[must_use]
+    pub const fn mappings(mut self, mappings: MappingFormat) -> Self {
+        self.mappings = mappings;
+
+        self
+    }
+
+    fn format_number(&self, number: &Number) -> String {
+        let Some(float) = number.as_f64().filter(|_| number.is_f64()) else {
+            return number.to_string();
+        };
+
+        match (self.float_precision, self.float_exponent) {
+            (Some(precision), true) => format!("{float:.precision$e}"),
+            (Some(precision), false) => format!("{float:.precision$}"),
+            (None, true) => format!("{float:e}"),
+            (None, false) => number.to_string(),
+        }
+    }
+}
+
+impl ValueFormatter for ConfigFormatter {
+    fn format(&self, value: &Value) -> String {
+        match value {
+            Value::Null => self.null_text.clone(),
+            Value::Bool(true) => self.true_text.clone(),
+            Value::Bool(false) => self.false_text.clone(),
+            Value::Number(number) => self.format_number(number),
+            Value::String(string) => string.clone(),
+            Value::Sequence(sequence) => sequence
+                .iter()
+                .map(|item| self.format(item))
+                .collect::<Vec<_>>()
+                .join(&self.separator),
+            Value::Mapping(_) => match self.mappings {
+                MappingFormat::Empty => String::new(),
+                // A mapping with keys JSON can't represent, such as sequences, renders nothing
+                MappingFormat::Json => serde_json::to_string(value).unwrap_or_default(),
+                MappingFormat::Yaml => {
+                    let mut yaml = String::new();
+                    write_inline_yaml(value, &mut yaml);
+
+                    yaml
+                }
+            },
+            Value::Tagged(tagged) => self.format(&tagged.value),
+        }
+    }
+}
+
+/// Writes `value` as YAML in flow style, all on one line
+fn write_inline_yaml(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(bool) => out.push_str(&bool.to_string()),
+        Value::Number(number) => out.push_str(&number.to_string()),
+        Value::String(string) => {
+            // Strings that would read back as something else, or that use YAML syntax, are
+            // quoted. JSON strings are valid double quoted YAML strings.
+            let is_plain = !string.is_empty()
+                && !string.contains(|c: char| ":#,[]{}&*!|>'\"%@`\\".contains(c) || c.is_control())
+                && string.trim() == string
+                && !string.starts_with(['-', '?'])
+                && serde_yaml::from_str::<Value>(string).ok().as_ref() == Some(value);
+
+            if is_plain {
+                out.push_str(string);
+            } else {
+                out.push_str(&serde_json::to_string(string).unwrap_or_default());
+            }
+        }
+        Value::Sequence(sequence) => {
+            out.push('[');
+            for (index, item) in sequence.iter().enumerate() {
+                if index > 0 {
+                    out.push_str(", ");
+                }
+                write_inline_yaml(item, out);
+            }
+            out.push(']');
+        }
+        Value::Mapping(mapping) => {
+            out.push('{');
+            for (index, (key, value)) in mapping.iter().enumerate() {
+                if index > 0 {
+                    out.push_str(", ");
+                }
+                write_inline_yaml(key, out);
+                out.push_str(": ");
+                write_inline_yaml(value, out);
+            }
+            out.push('}');
+        }
+        Value::Tagged(tagged) => {
+            let TaggedValue { tag, value } = tagged.as_ref();
+            out.push_str(&tag.to_string());
+            out.push(' ');
+            write_inline_yaml(value, out);
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::{location::Location, syntax_tree::SyntaxItem};
+
+/// What kind of tag a [`Reference`] comes from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ReferenceKind {
+    /// `{{name}}`
+    Variable,
+    /// `{{{name}}}` or `{{&name}}`
+    RawVariable,
+    /// `{{#name}}`
+    Section,
+    /// `{{^name}}`
+    InvertedSection,
+    /// `{{>name}}`
+    Partial,
+    /// `{{>*name}}`, where `name` is the data holding the partial name
+    DynamicPartial,
+    /// `{{<name}}`
+    Parent,
+}
+
+impl fmt::Display for ReferenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Variable => "variable",
+            Self::RawVariable => "raw_variable",
+            Self::Section => "section",
+            Self::InvertedSection => "inverted_section",
+            Self::Partial => "partial",
+            Self::DynamicPartial => "dynamic_partial",
+            Self::Parent => "parent",
+        })
+    }
+}
+
+/// A name used by a template, returned by
+/// [`Template::references`](crate::Template::references)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct Reference {
+    pub kind: ReferenceKind,
+    /// The name as written in the tag, such as `scheme.author`
+    pub name: String,
+    /// The names of the sections the tag is inside, outermost first. Names are looked up in the
+    /// data of these sections before the top level data.
+    pub path: Vec<String>,
+    pub location: Location,
+}
+
+impl Reference {
+    fn new(kind: ReferenceKind, name: &str, path: &[String], location: &Location) -> Self {
+        Self {
+            kind,
+            name: name.to_string(),
+            path: path.to_vec(),
+            location: location.clone(),
+        }
+    }
+}
+
+/// Adds the references in `items` to `references` in template order
+pub fn collect_references(
+    items: &[SyntaxItem],
+    path: &mut Vec<String>,
+    references: &mut Vec<Reference>,
+) {
+    for item in items {
+        match item {
+            SyntaxItem::EscapedVariable(tag, location) | SyntaxItem::Variable(tag, location) => {
+                let kind = if matches!(item, SyntaxItem::EscapedVariable(..)) {
+                    ReferenceKind::Variable
+                } else {
+                    ReferenceKind::RawVariable
+                };
+                references.push(Reference::new(kind, &tag.name, path, location));
+            }
+            SyntaxItem::Partial {
+                name,
+                is_dynamic,
+                location,
+                ..
+            } => {
+                let kind = if *is_dynamic {
+                    ReferenceKind::DynamicPartial
+                } else {
+                    ReferenceKind::Partial
+                };
+                references.push(Reference::new(kind, name, path, location));
+            }
+            SyntaxItem::Parent {
+                name,
+                items,
+                location,
+                ..
+            } => {
+                references.push(Reference::new(ReferenceKind::Parent, name, path, location));
+                collect_references(items, path, references);
+            }
+            SyntaxItem::Section {
+                name,
+                is_inverted,
+                items,
+                location,
+                ..
+            } => {
+                let kind = if *is_inverted {
+                    ReferenceKind::InvertedSection
+                } else {
+                    ReferenceKind::Section
+                };
+                references.push(Reference::new(kind, name, path, location));
+
+                path.push(name.clone());
+                collect_references(items, path, references);
+                path.pop();
+            }
+            SyntaxItem::Block { items, .. } => collect_references(items, path, references),
+            SyntaxItem::Text(_) | SyntaxItem::Comment { .. } | SyntaxItem::Delimiter { .. } => {}
+        }
+    }
+}
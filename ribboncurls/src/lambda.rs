@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+use std::fmt;
+
+type InterpolationFn = dyn Fn() -> String + Send + Sync;
+type SectionFn = dyn Fn(&str) -> String + Send + Sync;
+
+/// A Rust function standing in for a data value. The string it returns is rendered as a template
+/// against the current context.
+pub enum Lambda {
+    /// Called without arguments wherever the name is used
+    Interpolation(Box<InterpolationFn>),
+    /// Called with the unprocessed section content when the name is used as a section, or with an
+    /// empty string when used as a variable
+    Section(Box<SectionFn>),
+}
+
+impl Lambda {
+    pub fn call(&self, text: &str) -> String {
+        match self {
+            Self::Interpolation(lambda) => lambda(),
+            Self::Section(lambda) => lambda(text),
+        }
+    }
+}
+
+/// Named Rust functions that can be used in place of data values, following the optional
+/// [Mustache lambdas spec](https://github.com/mustache/spec/blob/master/specs/~lambdas.yml).
+///
+/// A lambda takes precedence over data with the same name.
+///
+/// ```rust
+/// let mut lambdas = ribboncurls::Lambdas::new();
+/// lambdas
+///     .interpolation("year", || "2024".to_string())
+///     .section("bold", |text| format!("<b>{text}</b>"));
+///
+/// let template = "{{#bold}}{{name}}{{/bold}} ({{year}})";
+/// let output = ribboncurls::render_with_lambdas(template, "name: Tinted", None, &lambdas).unwrap();
+/// assert_eq!(output, "<b>Tinted</b> (2024)");
+/// ```
+#[derive(Default)]
+pub struct Lambdas {
+    lambdas: HashMap<String, Lambda>,
+}
+
+impl Lambdas {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a lambda for use in variable tags such as `{{name}}`. The returned string is
+    /// rendered with the default delimiters.
+    pub fn interpolation<F>(&mut self, name: &str, lambda: F) -> &mut Self
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        self.lambdas
+            .insert(name.to_string(), Lambda::Interpolation(Box::new(lambda)));
+        self
+    }
+
+    /// Registers a lambda for use in section tags such as `{{#name}}...{{/name}}`. The lambda
+    /// receives the unprocessed section content and the returned string is rendered with the
+    /// delimiters active where the section was opened.
+    pub fn section<F>(&mut self, name: &str, lambda: F) -> &mut Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.lambdas
+            .insert(name.to_string(), Lambda::Section(Box::new(lambda)));
+        self
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&Lambda> {
+        self.lambdas.get(name)
+    }
+}
+
+impl fmt::Debug for Lambdas {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.lambdas.keys()).finish()
+    }
+}
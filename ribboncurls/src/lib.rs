@@ -1,13 +1,53 @@
 #![doc = include_str!("../README.md")]
 
+pub mod ast;
+#[cfg(feature = "colors")]
+pub mod color;
+mod data;
+mod delimiters;
+mod escape;
+#[cfg(feature = "filters")]
+mod filter;
+mod format;
+mod introspect;
+mod lambda;
+mod limits;
+mod location;
+mod output;
+mod partials;
+mod strict;
 mod syntax_tree;
+mod template;
 mod token;
 mod utils;
 
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+
+pub use data::DataFormat;
+pub use delimiters::Delimiters;
+pub use escape::{
+    Escaper, HtmlEscaper, JsonStringEscaper, NoEscaper, ShellSingleQuoteEscaper, XmlEscaper,
+};
+#[cfg(feature = "filters")]
+pub use filter::Filters;
+pub use format::{ConfigFormatter, MappingFormat, MustacheFormatter, ValueFormatter};
+pub use introspect::{Reference, ReferenceKind};
+pub use lambda::Lambdas;
+pub use limits::Limits;
+pub use location::Location;
+pub use output::LineEndings;
+use output::{EscapedOutput, IndentedOutput, Output, TrackedOutput};
+use partials::Partials;
+pub use partials::{DirectoryLoader, PartialLoader};
+use serde::Serialize;
 use serde_yaml::Value;
-use syntax_tree::{create_syntax_tree, SyntaxItem};
-use token::{tokenize, Token};
-use utils::{escape_html, get_newline_variant, get_regex_for_newline, Newline, NewlineRegex};
+pub use strict::StrictMode;
+use syntax_tree::{create_syntax_tree, get_text_indent, SyntaxItem, VariableTag};
+use template::data_from_yaml;
+pub use template::Template;
+use token::{tokenize, OpenTag, SectionSource};
+use utils::{get_regex_for_newline, strip_newline, NewlineRegex};
 
 const DEFAULT_LEFT_DELIMITER: &str = "{{";
 const DEFAULT_RIGHT_DELIMITER: &str = "}}";
@@ -15,38 +55,175 @@ const DEFAULT_RIGHT_DELIMITER: &str = "}}";
 #[non_exhaustive]
 #[derive(thiserror::Error, Debug)]
 pub enum RibboncurlsError {
-    #[error("missing delimiter")]
-    MissingDelimiter,
-    #[error("missing end tag")]
-    MissingEndTag,
-    #[error("missing data")]
-    MissingData,
-    #[error("string too large")]
-    StringSize,
-    #[error("bad tag")]
-    BadTag,
+    /// A set delimiter tag that doesn't set two delimiters
+    #[error("missing delimiter in \"{tag}\" at {location}")]
+    MissingDelimiter { tag: String, location: Location },
+    /// A set delimiter tag with delimiters that aren't allowed, such as ones containing `=`
+    #[error("bad delimiter in \"{tag}\": {message} at {location}")]
+    BadDelimiter {
+        tag: String,
+        message: String,
+        location: Location,
+    },
+    /// Starting [`Delimiters`] that can't be used, such as ones containing whitespace
+    #[error("invalid delimiters \"{delimiters}\": {message}")]
+    InvalidDelimiters { delimiters: String, message: String },
+    /// A tag that is opened but never closed with the `delimiter` end tag
+    #[error("missing end tag \"{delimiter}\" at {location}")]
+    MissingEndTag {
+        delimiter: String,
+        location: Location,
+    },
+    /// A close tag for `found` where the `expected` section should be closed first
+    #[error("expected close tag for section \"{expected}\" but found \"{found}\" at {location}")]
+    MismatchedSection {
+        expected: String,
+        found: String,
+        location: Location,
+    },
+    /// A section, parent or block `name` that is still open at the end of the template
+    #[error("section \"{name}\" is never closed, opened at {location}")]
+    UnclosedSection { name: String, location: Location },
+    /// A close tag for `name` when no section is open
+    #[error("close tag for section \"{name}\" that isn't open at {location}")]
+    UnexpectedCloseTag { name: String, location: Location },
+    /// In strict mode, a variable or section whose `path` isn't in the data
+    #[error("missing data \"{path}\" at {location}")]
+    MissingData { path: String, location: Location },
+    /// In strict mode, a partial that can't be found
+    #[error("missing partial \"{name}\" at {location}")]
+    MissingPartial { name: String, location: Location },
+    /// Rendered output that is larger than the `limit` in bytes set with [`Limits`]
+    #[error("output is larger than the limit of {limit} bytes")]
+    StringSize { limit: usize },
+    /// A partial or parent `name` nested deeper than the `limit` set with [`Limits`]
+    #[error("partial \"{name}\" is nested more than {limit} deep at {location}")]
+    PartialDepth {
+        name: String,
+        limit: usize,
+        location: Location,
+    },
+    /// A section, parent or block `name` nested deeper than the `limit` set with [`Limits`]
+    #[error("section \"{name}\" is nested more than {limit} deep at {location}")]
+    SectionDepth {
+        name: String,
+        limit: usize,
+        location: Location,
+    },
+    /// Sections rendered for more sequence items than the `limit` set with [`Limits`]
+    #[error("sections are iterated more than {limit} times at {location}")]
+    Iterations { limit: usize, location: Location },
+    /// A section, parent, block or partial `tag` without a name, such as `{{#}}`
+    #[error("bad tag \"{tag}\" at {location}")]
+    BadTag { tag: String, location: Location },
+    /// A variable tag whose filters can't be parsed
+    #[cfg(feature = "filters")]
+    #[error("bad filter in \"{tag}\": {message} at {location}")]
+    BadFilter {
+        tag: String,
+        message: String,
+        location: Location,
+    },
+    /// A filter `name` that isn't in the template's [`Filters`]
+    #[cfg(feature = "filters")]
+    #[error("unknown filter \"{name}\" at {location}")]
+    UnknownFilter { name: String, location: Location },
+    /// A filter `name` that returned an error
+    #[cfg(feature = "filters")]
+    #[error("filter \"{name}\" failed: {message} at {location}")]
+    Filter {
+        name: String,
+        message: String,
+        location: Location,
+    },
     #[error("bad input")]
     YamlParseError(#[from] serde_yaml::Error),
+    #[error("bad JSON input")]
+    JsonParseError(#[from] serde_json::Error),
+    #[error("bad TOML input")]
+    TomlParseError(#[from] toml::de::Error),
+    #[error("unable to load partial \"{name}\"")]
+    PartialLoad {
+        name: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("unable to write output")]
+    Write(#[source] std::io::Error),
+}
+
+impl RibboncurlsError {
+    /// Where in the template the error happened, for errors caused by the template itself
+    #[must_use]
+    pub const fn location(&self) -> Option<&Location> {
+        match self {
+            Self::MissingDelimiter { location, .. }
+            | Self::BadDelimiter { location, .. }
+            | Self::MissingEndTag { location, .. }
+            | Self::MismatchedSection { location, .. }
+            | Self::UnclosedSection { location, .. }
+            | Self::UnexpectedCloseTag { location, .. }
+            | Self::MissingData { location, .. }
+            | Self::MissingPartial { location, .. }
+            | Self::PartialDepth { location, .. }
+            | Self::SectionDepth { location, .. }
+            | Self::Iterations { location, .. }
+            | Self::BadTag { location, .. } => Some(location),
+            #[cfg(feature = "filters")]
+            Self::BadFilter { location, .. }
+            | Self::UnknownFilter { location, .. }
+            | Self::Filter { location, .. } => Some(location),
+            _ => None,
+        }
+    }
 }
 
 pub struct TokenCtx {
     left_delimiter: String,
     right_delimiter: String,
-    section_stack: Vec<Token>,
+    section_stack: Vec<OpenTag>,
+    /// Name of the partial being tokenized, for error locations
+    partial: Option<String>,
+    max_section_depth: usize,
+}
+
+impl TokenCtx {
+    fn new(delimiters: &Delimiters, partial: Option<String>, max_section_depth: usize) -> Self {
+        Self {
+            left_delimiter: delimiters.left().to_string(),
+            right_delimiter: delimiters.right().to_string(),
+            section_stack: Vec::new(),
+            partial,
+            max_section_depth,
+        }
+    }
 }
 
 pub struct SyntaxCtx {
     is_root: bool,
-    newline: Newline,
 }
 
 #[derive(Debug)]
-struct RenderCtx {
+struct RenderCtx<'a> {
     data_stack: Vec<Value>,
-    partials: Value,
+    partials: &'a Partials,
+    lambdas: &'a Lambdas,
+    #[cfg(feature = "filters")]
+    filters: &'a Filters,
+    strict: Option<&'a StrictMode>,
+    escaper: &'a dyn Escaper,
+    formatter: &'a dyn ValueFormatter,
+    blocks: HashMap<String, Vec<SyntaxItem>>,
     section_path: Vec<String>,
-    newline: Newline,
-    indent: u8,
+    limits: &'a Limits,
+    /// How many partials deep rendering is
+    partial_depth: usize,
+    /// How many sections and blocks deep rendering is, counting those in every partial
+    section_depth: usize,
+    /// How many sequence items sections have been rendered for
+    iterations: usize,
+    /// The whitespace added to each line of the partials being rendered
+    indent: String,
 }
 
 /// Renders a template string using YAML data and optional partials.
@@ -62,31 +239,70 @@ pub fn render(
     data: &str,
     partials: Option<&str>,
 ) -> Result<String, RibboncurlsError> {
-    let data_stack = vec![serde_yaml::from_str(data).unwrap_or(Value::String(data.to_string()))];
-    let mut ctx = TokenCtx {
-        left_delimiter: DEFAULT_LEFT_DELIMITER.to_string(),
-        right_delimiter: DEFAULT_RIGHT_DELIMITER.to_string(),
-        section_stack: Vec::new(),
-    };
-    let tokens = tokenize(template, &mut ctx)?;
-    let syntax_ctx = SyntaxCtx {
-        is_root: true,
-        newline: get_newline_variant(template),
-    };
-    let syntax_tree = create_syntax_tree(&tokens, &syntax_ctx)?;
-    let mut render_context = RenderCtx {
-        data_stack,
-        partials: serde_yaml::from_str(partials.unwrap_or("null"))?,
-        section_path: vec![],
-        newline: get_newline_variant(template),
-        indent: 0,
-    };
-    render_syntax_tree(&syntax_tree, &mut render_context)
+    render_with_lambdas(template, data, partials, &Lambdas::default())
+}
+
+/// Renders a template string using YAML data, optional partials and [`Lambdas`].
+///
+/// # Errors
+///
+/// Returns a [`RibboncurlsError`] if:
+/// - The template, or a string returned by a lambda, cannot be tokenized or parsed.
+/// - The data or partials cannot be deserialized from YAML.
+/// - Rendering fails due to invalid template structure.
+pub fn render_with_lambdas(
+    template: &str,
+    data: &str,
+    partials: Option<&str>,
+    lambdas: &Lambdas,
+) -> Result<String, RibboncurlsError> {
+    Template::compile(template)?
+        .with_partials(partials.unwrap_or("null"))?
+        .render_with_lambdas(data_from_yaml(data), lambdas)
 }
 
-/// Renders a parsed template syntax tree into a String.
+/// Renders a template string using any serializable data, such as a struct or a
+/// [`serde_yaml::Value`], and a map of partial names to templates.
 ///
-/// This function walks through each item in the syntax tree and builds the final
+/// ```rust
+/// #[derive(serde::Serialize)]
+/// struct Scheme {
+///     name: &'static str,
+/// }
+///
+/// let partials = std::collections::HashMap::from([("footer".to_string(), "!".to_string())]);
+/// let output = ribboncurls::render_with("{{name}}{{> footer}}", &Scheme { name: "Tinted" }, &partials)?;
+/// assert_eq!(output, "Tinted!");
+/// # Ok::<(), ribboncurls::RibboncurlsError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns a [`RibboncurlsError`] if:
+/// - The template or a partial cannot be tokenized or parsed.
+/// - The data cannot be serialized.
+pub fn render_with<T, S>(
+    template: &str,
+    data: &T,
+    partials: &HashMap<String, String, S>,
+) -> Result<String, RibboncurlsError>
+where
+    T: Serialize + ?Sized,
+    S: BuildHasher,
+{
+    let partials = partials
+        .iter()
+        .map(|(name, partial)| (name.clone(), partial.clone()))
+        .collect();
+
+    Template::compile(template)?
+        .with_partial_map(partials)
+        .render_with(data)
+}
+
+/// Renders a parsed template syntax tree into `out`.
+///
+/// This function walks through each item in the syntax tree and writes the final
 /// rendered output. It handles plain text (including indentation for partials),
 /// variables (escaped and unescaped), sections and inverted sections, partials,
 /// and comments. The provided `ctx` is updated as sections and partials are
@@ -104,85 +320,57 @@ pub fn render(
 fn render_syntax_tree(
     syntax_tree: &[SyntaxItem],
     ctx: &mut RenderCtx,
-) -> Result<String, RibboncurlsError> {
-    let re_starts_with_newline_followed_by_whitespace_until_end = get_regex_for_newline(
-        NewlineRegex::StartsWithNewlineFollowedByWhitespaceUntilEnd,
-        ctx.newline,
-    );
-    let mut output = String::new();
-
-    for (index, node) in syntax_tree.iter().enumerate() {
+    out: &mut dyn Output,
+) -> Result<(), RibboncurlsError> {
+    for node in syntax_tree {
         match node {
-            SyntaxItem::Text(content) => {
-                // Indent if indent exists (for partial)
-                if ctx.indent > 0 {
-                    if let Some(updated) = content.strip_prefix('\n') {
-                        let mut indent_string = String::from('\n');
-
-                        // The standalone partial removes the following newline and the last item
-                        // here replaces that, but it should not contain the indent
-                        if index != syntax_tree.len() - 1
-                            || !re_starts_with_newline_followed_by_whitespace_until_end
-                                .is_match(content)
-                        {
-                            for _ in 0..ctx.indent {
-                                indent_string.push(' ');
-                            }
-                        }
-
-                        output.push_str(format!("{indent_string}{updated}").as_str());
-                    } else if index == 0 {
-                        let mut indent_string = String::new();
-                        for _ in 0..ctx.indent {
-                            indent_string.push(' ');
-                        }
-
-                        output.push_str(format!("{indent_string}{content}").as_str());
-                    }
-                } else {
-                    output.push_str(content);
-                }
+            SyntaxItem::Text(content) => write_indented_text(content, ctx, out)?,
+            SyntaxItem::EscapedVariable(tag, location) => {
+                render_variable(ctx, tag, location, true, out)?;
             }
-            SyntaxItem::EscapedVariable(content) => {
-                if let Some(value) = get_value_from_context(ctx, content.as_str()) {
-                    output.push_str(&escape_html(&serde_yaml_value_to_string(value)));
-                }
-            }
-            SyntaxItem::Variable(content) => {
-                if let Some(value) = get_value_from_context(ctx, content.as_str()) {
-                    output.push_str(&serde_yaml_value_to_string(value));
-                }
+            SyntaxItem::Variable(tag, location) => {
+                render_variable(ctx, tag, location, false, out)?;
             }
             SyntaxItem::Partial {
-                name: partial_name,
+                name,
                 indent,
                 is_standalone: _,
+                is_dynamic,
+                location,
             } => {
-                if let Some(partial_data) = ctx.partials.clone().get(partial_name) {
-                    let mut token_ctx = TokenCtx {
-                        left_delimiter: DEFAULT_LEFT_DELIMITER.to_string(),
-                        right_delimiter: DEFAULT_RIGHT_DELIMITER.to_string(),
-                        section_stack: Vec::new(),
-                    };
-                    let partial_tokens = tokenize(
-                        partial_data
-                            .as_str()
-                            .expect("Unable to extract string from serde_yaml::Value"),
-                        &mut token_ctx,
-                    )?;
-                    let syntax_ctx = SyntaxCtx {
-                        is_root: false,
-                        newline: ctx.newline,
-                    };
-                    let original_indent = ctx.indent;
-                    ctx.indent = *indent;
-                    let tree = create_syntax_tree(&partial_tokens, &syntax_ctx)?;
-                    let rendered = render_syntax_tree(&tree, ctx)?;
-                    ctx.indent = original_indent;
-
-                    output.push_str(&rendered);
+                if *is_dynamic {
+                    // A dynamic name that doesn't resolve to a string renders nothing, like a
+                    // missing partial
+                    if let Some(Value::String(name)) = get_value_from_context(ctx, name).cloned() {
+                        render_partial(ctx, &name, indent, location, out)?;
+                    } else {
+                        check_missing_data(ctx, name, location)?;
+                    }
+                } else {
+                    render_partial(ctx, name, indent, location, out)?;
                 }
             }
+            SyntaxItem::Parent {
+                name,
+                items,
+                indent,
+                is_standalone: _,
+                location,
+            } => {
+                render_parent(ctx, name, items, indent, location, out)?;
+            }
+            SyntaxItem::Block {
+                name,
+                items,
+                indent,
+                open_is_standalone,
+                closed_is_standalone: _,
+                location,
+            } => {
+                render_nested(ctx, name, location, |ctx| {
+                    render_block(ctx, name, items, indent, *open_is_standalone, out)
+                })?;
+            }
             SyntaxItem::Comment { is_standalone: _ }
             | SyntaxItem::Delimiter { is_standalone: _ } => {}
             SyntaxItem::Section {
@@ -191,15 +379,236 @@ fn render_syntax_tree(
                 is_inverted,
                 open_is_standalone: _,
                 closed_is_standalone: _,
+                source,
+                location,
             } => {
-                let section_output = render_syntax_tree_section(ctx, name, items, *is_inverted)?;
-
-                output.push_str(&section_output);
+                render_nested(ctx, name, location, |ctx| {
+                    render_syntax_tree_section(
+                        ctx,
+                        name,
+                        items,
+                        *is_inverted,
+                        source,
+                        location,
+                        out,
+                    )
+                })?;
             }
         }
     }
 
-    Ok(output)
+    Ok(())
+}
+
+/// Renders the `name` section or block with `render`, failing if it is nested deeper than the
+/// limit. Templates and partials are only checked on their own when they are parsed, so this
+/// catches partials that are included from inside sections going over the limit together.
+fn render_nested(
+    ctx: &mut RenderCtx,
+    name: &str,
+    location: &Location,
+    render: impl FnOnce(&mut RenderCtx) -> Result<(), RibboncurlsError>,
+) -> Result<(), RibboncurlsError> {
+    if ctx.section_depth >= ctx.limits.max_section_depth() {
+        return Err(RibboncurlsError::SectionDepth {
+            name: name.to_string(),
+            limit: ctx.limits.max_section_depth(),
+            location: location.clone(),
+        });
+    }
+
+    ctx.section_depth += 1;
+    let rendered = render(ctx);
+    ctx.section_depth -= 1;
+
+    rendered
+}
+
+/// Renders the `name` partial. The lines of a standalone partial are indented by `indent` on top
+/// of the indentation of any partials it is in.
+fn render_partial(
+    ctx: &mut RenderCtx,
+    name: &str,
+    indent: &str,
+    location: &Location,
+    out: &mut dyn Output,
+) -> Result<(), RibboncurlsError> {
+    let Some(tree) = ctx.partials.get(name)? else {
+        return match ctx.strict {
+            Some(strict) if !strict.allows_missing(name) => Err(RibboncurlsError::MissingPartial {
+                name: name.to_string(),
+                location: location.clone(),
+            }),
+            _ => Ok(()),
+        };
+    };
+    if ctx.partial_depth >= ctx.limits.max_partial_depth() {
+        return Err(RibboncurlsError::PartialDepth {
+            name: name.to_string(),
+            limit: ctx.limits.max_partial_depth(),
+            location: location.clone(),
+        });
+    }
+    let re_starts_with_newline_followed_by_whitespace_until_end =
+        get_regex_for_newline(NewlineRegex::StartsWithNewlineFollowedByWhitespaceUntilEnd);
+    // A standalone partial tag takes its newline with it, so a last line that is only a newline
+    // ends the line the tag was on and the next line continues with the enclosing indentation
+    let (items, last_line) = match tree.split_last() {
+        Some((SyntaxItem::Text(text), items))
+            if re_starts_with_newline_followed_by_whitespace_until_end.is_match(text) =>
+        {
+            (items, Some(text))
+        }
+        _ => (tree.as_slice(), None),
+    };
+
+    // The first line follows the indentation already written for the line the tag is on
+    if !tree.is_empty() {
+        out.write_str(indent)?;
+    }
+
+    let original_indent = ctx.indent.clone();
+    ctx.indent.push_str(indent);
+    ctx.partial_depth += 1;
+    let rendered = render_syntax_tree(items, ctx, out);
+    ctx.partial_depth -= 1;
+    ctx.indent = original_indent;
+    rendered?;
+
+    if let Some(text) = last_line {
+        write_indented_text(text, ctx, out)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a line of text, adding the partial indentation after its leading newline
+fn write_indented_text(
+    text: &str,
+    ctx: &RenderCtx,
+    out: &mut dyn Output,
+) -> Result<(), RibboncurlsError> {
+    match strip_newline(text) {
+        Some((newline, line)) if !ctx.indent.is_empty() => {
+            out.write_str(newline)?;
+            out.write_str(&ctx.indent)?;
+            out.write_str(line)
+        }
+        _ => out.write_str(text),
+    }
+}
+
+/// Renders the `name` partial with the blocks in `items` overriding the partial's own blocks.
+/// Blocks already overridden further up take precedence over `items`.
+fn render_parent(
+    ctx: &mut RenderCtx,
+    name: &str,
+    items: &[SyntaxItem],
+    indent: &str,
+    location: &Location,
+    out: &mut dyn Output,
+) -> Result<(), RibboncurlsError> {
+    let mut blocks: HashMap<String, Vec<SyntaxItem>> = items
+        .iter()
+        .filter_map(|item| match item {
+            SyntaxItem::Block { name, items, .. } => Some((name.clone(), items.clone())),
+            _ => None,
+        })
+        .collect();
+    blocks.extend(ctx.blocks.clone());
+
+    let original_blocks = std::mem::replace(&mut ctx.blocks, blocks);
+    let rendered = render_partial(ctx, name, indent, location, out);
+    ctx.blocks = original_blocks;
+
+    rendered
+}
+
+/// Renders the override for the `name` block if there is one, otherwise the block's default
+/// `items`. Overrides are re-indented to match the block, which for a standalone block is the
+/// indentation of its default content when there is any.
+fn render_block(
+    ctx: &mut RenderCtx,
+    name: &str,
+    items: &[SyntaxItem],
+    indent: &str,
+    is_standalone: bool,
+    out: &mut dyn Output,
+) -> Result<(), RibboncurlsError> {
+    // The override is taken out while it renders so that it can't expand into itself
+    let Some(block_override) = ctx.blocks.remove(name) else {
+        return render_syntax_tree(items, ctx, out);
+    };
+    let indent = if is_standalone {
+        get_text_indent(items).unwrap_or(indent)
+    } else {
+        indent
+    };
+
+    let mut indented_out = IndentedOutput::new(out, indent, is_standalone);
+    let result = render_syntax_tree(&block_override, ctx, &mut indented_out);
+    ctx.blocks.insert(name.to_string(), block_override);
+    result?;
+
+    indented_out.finish()
+}
+
+/// Calls and renders the lambda registered under `name`, if any, for a variable tag, escaping the
+/// output as it is written if `escape` is set. Lambda output for variables is always rendered with
+/// the default delimiters.
+fn render_variable_lambda(
+    ctx: &mut RenderCtx,
+    name: &str,
+    escape: bool,
+    out: &mut dyn Output,
+) -> Result<(), RibboncurlsError> {
+    let Some(lambda) = ctx.lambdas.get(name) else {
+        return Ok(());
+    };
+    let text = lambda.call("");
+
+    let escaper = ctx.escaper;
+    let mut escaped_out;
+    let out: &mut dyn Output = if escape {
+        escaped_out = EscapedOutput::new(out, escaper);
+        &mut escaped_out
+    } else {
+        out
+    };
+    render_lambda_output(
+        &text,
+        DEFAULT_LEFT_DELIMITER,
+        DEFAULT_RIGHT_DELIMITER,
+        ctx,
+        out,
+    )
+}
+
+/// Renders the string returned by a lambda as a template against the current context
+fn render_lambda_output(
+    text: &str,
+    left_delimiter: &str,
+    right_delimiter: &str,
+    ctx: &mut RenderCtx,
+    out: &mut dyn Output,
+) -> Result<(), RibboncurlsError> {
+    let mut token_ctx = TokenCtx {
+        left_delimiter: left_delimiter.to_string(),
+        right_delimiter: right_delimiter.to_string(),
+        section_stack: Vec::new(),
+        partial: None,
+        max_section_depth: ctx.limits.max_section_depth(),
+    };
+    let tokens = tokenize(text, &mut token_ctx)?;
+    let syntax_ctx = SyntaxCtx { is_root: false };
+    let tree = create_syntax_tree(&tokens, &syntax_ctx)?;
+
+    // Lambda output is inline, so it shouldn't pick up partial indentation
+    let original_indent = std::mem::take(&mut ctx.indent);
+    let rendered = render_syntax_tree(&tree, ctx, out);
+    ctx.indent = original_indent;
+
+    rendered
 }
 
 fn render_syntax_tree_section(
@@ -207,15 +616,36 @@ fn render_syntax_tree_section(
     name: &str,
     items: &[SyntaxItem],
     is_inverted: bool,
-) -> Result<String, RibboncurlsError> {
-    let mut output = String::new();
+    source: &SectionSource,
+    location: &Location,
+    out: &mut dyn Output,
+) -> Result<(), RibboncurlsError> {
+    // Lambdas are always truthy, so an inverted section over a lambda renders nothing
+    if let Some(lambda) = ctx.lambdas.get(name) {
+        if is_inverted {
+            return Ok(());
+        }
+
+        return render_lambda_output(
+            &lambda.call(source.raw()),
+            &source.left_delimiter,
+            &source.right_delimiter,
+            ctx,
+            out,
+        );
+    }
+
+    // Inverted sections are how templates check for missing data, so only normal sections
+    // need the data
+    if !is_inverted && get_value_from_context(ctx, name).is_none() {
+        check_missing_data(ctx, name, location)?;
+    }
+
     // Sequence of sequences
     // ---------------------
     // A sequence of sequences behaves differently the the rest of the sections, so if
     // it matches, render and continue
-    if let Some(sequence_output) = render_sequence_of_sequences(name, ctx, items)? {
-        output.push_str(&sequence_output);
-    } else {
+    if !render_sequence_of_sequences(name, ctx, items, location, out)? {
         // All other sections
         // ------------------
         ctx.section_path.push(name.to_string());
@@ -240,26 +670,21 @@ fn render_syntax_tree_section(
         match (iterator_option, is_inverted) {
             (Some(Value::Sequence(section_context)), false) => {
                 for item in section_context {
+                    count_iteration(ctx, location)?;
                     ctx.data_stack.push(item);
 
                     match (&section_context_option, is_inverted) {
                         (Some(value), false) => {
                             if is_value_truthy(value) {
-                                let section_output = render_syntax_tree(items, ctx)?;
-
-                                output.push_str(&section_output);
+                                render_syntax_tree(items, ctx, out)?;
                             }
                         }
                         (None, true) => {
-                            let section_output = render_syntax_tree(items, ctx)?;
-
-                            output.push_str(&section_output);
+                            render_syntax_tree(items, ctx, out)?;
                         }
                         (Some(value), true) => {
                             if is_value_falsy(value) && !matches!(value, Value::Mapping(_)) {
-                                let section_output = render_syntax_tree(items, ctx)?;
-
-                                output.push_str(&section_output);
+                                render_syntax_tree(items, ctx, out)?;
                             }
                         }
                         (None, false) => {}
@@ -271,21 +696,15 @@ fn render_syntax_tree_section(
             _ => match (section_context_option, is_inverted) {
                 (Some(value), false) => {
                     if is_value_truthy(&value) {
-                        let section_output = render_syntax_tree(items, ctx)?;
-
-                        output.push_str(&section_output);
+                        render_syntax_tree(items, ctx, out)?;
                     }
                 }
                 (None, true) => {
-                    let section_output = render_syntax_tree(items, ctx)?;
-
-                    output.push_str(&section_output);
+                    render_syntax_tree(items, ctx, out)?;
                 }
                 (Some(value), true) => {
                     if is_value_falsy(&value) {
-                        let section_output = render_syntax_tree(items, ctx)?;
-
-                        output.push_str(&section_output);
+                        render_syntax_tree(items, ctx, out)?;
                     }
                 }
                 (None, false) => {}
@@ -298,22 +717,84 @@ fn render_syntax_tree_section(
         ctx.section_path.pop();
     }
 
-    Ok(output)
+    Ok(())
 }
 
-fn serde_yaml_value_to_string(value: &Value) -> String {
-    match value {
-        Value::Number(n) => n.to_string(),
-        Value::String(s) => s.to_owned(),
-        Value::Bool(b) => {
-            if *b {
-                b.to_string()
-            } else {
-                String::new()
-            }
+/// Renders the value or lambda output for the variable `tag`, escaped if `escape` is set
+fn render_variable(
+    ctx: &mut RenderCtx,
+    tag: &VariableTag,
+    location: &Location,
+    escape: bool,
+    out: &mut dyn Output,
+) -> Result<(), RibboncurlsError> {
+    let name = tag.name.as_str();
+    #[cfg(feature = "filters")]
+    let filters = &tag.filters;
+
+    let value = if ctx.lambdas.get(name).is_some() {
+        // Filters need the whole value, so lambda output is only written as it renders without
+        // them
+        #[cfg(feature = "filters")]
+        let streams = filters.is_empty();
+        #[cfg(not(feature = "filters"))]
+        let streams = true;
+        if streams {
+            return render_variable_lambda(ctx, name, escape, out);
         }
 
-        _ => String::new(),
+        let mut rendered = String::new();
+        render_variable_lambda(ctx, name, false, &mut rendered)?;
+        Some(rendered)
+    } else {
+        get_value_from_context(ctx, name).map(|value| ctx.formatter.format(value))
+    };
+
+    // A missing value renders nothing, unless a `default` filter gives it one
+    #[cfg(feature = "filters")]
+    let value = match value {
+        None if !filters.iter().any(filter::FilterCall::is_default) => None,
+        value => Some(
+            ctx.filters
+                .apply(value.unwrap_or_default(), filters, location)?,
+        ),
+    };
+    let Some(rendered) = value else {
+        return check_missing_data(ctx, name, location);
+    };
+
+    if escape {
+        out.write_str(&ctx.escaper.escape(&rendered))
+    } else {
+        out.write_str(&rendered)
+    }
+}
+
+/// Counts a section rendered for a sequence item, failing once there are more than the limit
+fn count_iteration(ctx: &mut RenderCtx, location: &Location) -> Result<(), RibboncurlsError> {
+    ctx.iterations += 1;
+
+    match ctx.limits.max_iterations() {
+        Some(limit) if ctx.iterations > limit => Err(RibboncurlsError::Iterations {
+            limit,
+            location: location.clone(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// In strict mode, returns an error for the missing `path` unless it is allowed to be missing
+fn check_missing_data(
+    ctx: &RenderCtx,
+    path: &str,
+    location: &Location,
+) -> Result<(), RibboncurlsError> {
+    match ctx.strict {
+        Some(strict) if !strict.allows_missing(path) => Err(RibboncurlsError::MissingData {
+            path: path.to_string(),
+            location: location.clone(),
+        }),
+        _ => Ok(()),
     }
 }
 
@@ -422,23 +903,26 @@ fn is_value_truthy(value: &Value) -> bool {
     !is_value_falsy(value)
 }
 
+/// Renders the `.` section for each item of a sequence at the top of the data stack. Returns
+/// whether anything was written, since the section is rendered like any other if not.
 fn render_sequence_of_sequences(
     name: &str,
     ctx: &mut RenderCtx,
     items: &[SyntaxItem],
-) -> Result<Option<String>, RibboncurlsError> {
-    let mut value = String::new();
+    location: &Location,
+    out: &mut dyn Output,
+) -> Result<bool, RibboncurlsError> {
+    let mut out = TrackedOutput::new(out);
     if name == "." {
         if let Some(Value::Sequence(sequence)) = ctx.data_stack.last() {
             let sequence_clone = sequence.clone();
             for item in sequence_clone {
-                let name = serde_yaml_value_to_string(&item);
+                count_iteration(ctx, location)?;
+                let name = MustacheFormatter.format(&item);
                 ctx.section_path.push(name);
                 ctx.data_stack.push(item.clone());
                 if is_value_truthy(&item) {
-                    let section_output = render_syntax_tree(items, ctx)?;
-
-                    value.push_str(&section_output);
+                    render_syntax_tree(items, ctx, &mut out)?;
                 }
                 ctx.data_stack.pop();
                 ctx.section_path.pop();
@@ -446,9 +930,5 @@ fn render_sequence_of_sequences(
         }
     }
 
-    if value.is_empty() {
-        Ok(None)
-    } else {
-        Ok(Some(value))
-    }
+    Ok(out.written)
 }
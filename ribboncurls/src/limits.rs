@@ -0,0 +1,92 @@
+/// Limits on how much work rendering a template can do. Used with
+/// [`Template::with_limits`](crate::Template::with_limits).
+///
+/// Templates from untrusted sources that go over a limit fail with an error instead of exhausting
+/// the stack or memory. By default partials can be nested 100 deep, which stops a partial that
+/// includes itself, sections can be nested 100 deep, and output size and section iterations are
+/// unlimited.
+#[derive(Clone, Debug)]
+pub struct Limits {
+    partial_depth: usize,
+    section_depth: usize,
+    output_bytes: Option<usize>,
+    iterations: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            partial_depth: DEFAULT_PARTIAL_DEPTH,
+            section_depth: DEFAULT_SECTION_DEPTH,
+            output_bytes: None,
+            iterations: None,
+        }
+    }
+}
+
+const DEFAULT_PARTIAL_DEPTH: usize = 100;
+const DEFAULT_SECTION_DEPTH: usize = 100;
+
+impl Limits {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how deep partials and parents can be nested, where a partial in the template is 1 deep
+    #[must_use]
+    pub const fn partial_depth(mut self, depth: usize) -> Self {
+        self.partial_depth = depth;
+
+        self
+    }
+
+    /// Sets how deep sections, inverted sections, parents and blocks can be nested in a template
+    /// or partial, where a section in the template is 1 deep. Templates are checked when they are
+    /// compiled, so use [`Template::compile_with_limits`](crate::Template::compile_with_limits)
+    /// to raise it for the template itself. When rendering, the sections and blocks in a partial
+    /// add to the ones around the partial tag.
+    #[must_use]
+    pub const fn section_depth(mut self, depth: usize) -> Self {
+        self.section_depth = depth;
+
+        self
+    }
+
+    /// Sets the most bytes the rendered output can have
+    #[must_use]
+    pub const fn output_bytes(mut self, bytes: usize) -> Self {
+        self.output_bytes = Some(bytes);
+
+        self
+    }
+
+    /// Sets the most times sections can be rendered for the items of sequences, in total across
+    /// the whole template
+    #[must_use]
+    pub const fn iterations(mut self, iterations: usize) -> Self {
+        self.iterations = Some(iterations);
+
+        self
+    }
+
+    #[must_use]
+    pub const fn max_partial_depth(&self) -> usize {
+        self.partial_depth
+    }
+
+    #[must_use]
+    pub const fn max_section_depth(&self) -> usize {
+        self.section_depth
+    }
+
+    #[must_use]
+    pub const fn max_output_bytes(&self) -> Option<usize> {
+        self.output_bytes
+    }
+
+    #[must_use]
+    pub const fn max_iterations(&self) -> Option<usize> {
+        self.iterations
+    }
+}
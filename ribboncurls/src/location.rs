@@ -0,0 +1,117 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// Where an error happened in a template
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct Location {
+    /// Byte offset of the start of the offending tag
+    pub offset: usize,
+    /// Length of the offending tag in bytes
+    pub len: usize,
+    /// Line number, starting at 1
+    pub line: usize,
+    /// Column in characters, starting at 1
+    pub column: usize,
+    /// Name of the partial the template came from, or `None` for the template being rendered
+    pub partial: Option<String>,
+}
+
+impl Location {
+    /// Shows the line of `template` containing the error with carets under the offending tag.
+    /// `template` should be the partial when [`Location::partial`] is set.
+    ///
+    /// ```rust
+    /// let template = "Hello\n{{#name}}{{/nmae}}";
+    /// let err = ribboncurls::render(template, "name: Tinted", None).unwrap_err();
+    /// let snippet = err.location().unwrap().snippet(template);
+    ///
+    /// assert_eq!(snippet, "2 | {{#name}}{{/nmae}}\n  |          ^^^^^^^^^");
+    /// ```
+    #[must_use]
+    pub fn snippet(&self, template: &str) -> String {
+        let line = template
+            .lines()
+            .nth(self.line - 1)
+            .unwrap_or_default()
+            .trim_end_matches('\r');
+        let line_number = self.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+        // Tabs are kept so that the carets line up with the tag
+        let padding: String = line
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        // Carets stop at the end of the line for tags spanning several lines
+        let tag_width = template
+            .get(self.offset..self.offset + self.len)
+            .and_then(|tag| tag.lines().next())
+            .map_or(1, |tag| tag.chars().count().max(1));
+        let carets = "^".repeat(tag_width);
+
+        format!("{line_number} | {line}\n{gutter} | {padding}{carets}")
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)?;
+
+        if let Some(partial) = &self.partial {
+            write!(f, " in partial \"{partial}\"")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Finds the locations of tags in a template. Tags are usually looked up in order, so the cursor
+/// only scans the template between one tag and the next.
+pub struct LocationCursor<'a> {
+    template: &'a str,
+    partial: Option<String>,
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> LocationCursor<'a> {
+    pub const fn new(template: &'a str, partial: Option<String>) -> Self {
+        Self {
+            template,
+            partial,
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    /// Returns the location of the `len` bytes long tag at `offset`
+    pub fn location(&mut self, offset: usize, len: usize) -> Location {
+        if offset < self.offset {
+            self.offset = 0;
+            self.line = 1;
+            self.column = 1;
+        }
+
+        for c in self.template[self.offset..offset].chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+        self.offset = offset;
+
+        Location {
+            offset,
+            len,
+            line: self.line,
+            column: self.column,
+            partial: self.partial.clone(),
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use std::fmt;
+use std::io;
+
+use crate::{Escaper, RibboncurlsError};
+
+/// Where rendered output is written. Sections and partials write into the same output as the
+/// template that contains them.
+pub trait Output {
+    fn write_str(&mut self, text: &str) -> Result<(), RibboncurlsError>;
+}
+
+impl Output for String {
+    fn write_str(&mut self, text: &str) -> Result<(), RibboncurlsError> {
+        self.push_str(text);
+
+        Ok(())
+    }
+}
+
+/// Writes rendered output to a [`std::io::Write`]
+pub struct IoOutput<'a, W: io::Write + ?Sized>(pub &'a mut W);
+
+impl<W: io::Write + ?Sized> Output for IoOutput<'_, W> {
+    fn write_str(&mut self, text: &str) -> Result<(), RibboncurlsError> {
+        self.0
+            .write_all(text.as_bytes())
+            .map_err(RibboncurlsError::Write)
+    }
+}
+
+/// Writes rendered output to a [`std::fmt::Write`]
+pub struct FmtOutput<'a, W: fmt::Write + ?Sized>(pub &'a mut W);
+
+impl<W: fmt::Write + ?Sized> Output for FmtOutput<'_, W> {
+    fn write_str(&mut self, text: &str) -> Result<(), RibboncurlsError> {
+        self.0
+            .write_str(text)
+            .map_err(|err| RibboncurlsError::Write(io::Error::new(io::ErrorKind::Other, err)))
+    }
+}
+
+/// The line endings of rendered output, set with
+/// [`Template::with_line_endings`](crate::Template::with_line_endings)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LineEndings {
+    /// Leaves line endings as they are in the template, partials and data
+    #[default]
+    Preserve,
+    /// Writes every `\r\n` as `\n`
+    Lf,
+    /// Writes every `\n` as `\r\n`
+    Crlf,
+}
+
+impl LineEndings {
+    /// The newline to write, or `None` to leave line endings as they are
+    pub(crate) const fn newline(self) -> Option<&'static str> {
+        match self {
+            Self::Preserve => None,
+            Self::Lf => Some("\n"),
+            Self::Crlf => Some("\r\n"),
+        }
+    }
+}
+
+/// Rewrites the line endings of everything written to `out`. A `\r` at the end of a write is held
+/// back until the next write, since it may be the start of a `\r\n`.
+pub struct NewlineOutput<'a> {
+    out: &'a mut dyn Output,
+    newline: &'static str,
+    pending_cr: bool,
+}
+
+impl<'a> NewlineOutput<'a> {
+    /// Writes to `out` with every line ending as `newline`
+    pub fn new(out: &'a mut dyn Output, newline: &'static str) -> Self {
+        Self {
+            out,
+            newline,
+            pending_cr: false,
+        }
+    }
+
+    /// Writes a `\r` held back from the last write
+    pub fn finish(self) -> Result<(), RibboncurlsError> {
+        if self.pending_cr {
+            self.out.write_str("\r")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Output for NewlineOutput<'_> {
+    fn write_str(&mut self, text: &str) -> Result<(), RibboncurlsError> {
+        let mut text = text;
+        if std::mem::take(&mut self.pending_cr) {
+            match text.strip_prefix('\n') {
+                Some(rest) => {
+                    self.out.write_str(self.newline)?;
+                    text = rest;
+                }
+                None => self.out.write_str("\r")?,
+            }
+        }
+        if let Some(rest) = text.strip_suffix('\r') {
+            self.pending_cr = true;
+            text = rest;
+        }
+
+        self.out
+            .write_str(&text.replace("\r\n", "\n").replace('\n', self.newline))
+    }
+}
+
+/// Fails with [`RibboncurlsError::StringSize`] once more than `limit` bytes would be written to
+/// `out`
+pub struct LimitedOutput<'a> {
+    out: &'a mut dyn Output,
+    limit: usize,
+    written: usize,
+}
+
+impl<'a> LimitedOutput<'a> {
+    pub fn new(out: &'a mut dyn Output, limit: usize) -> Self {
+        Self {
+            out,
+            limit,
+            written: 0,
+        }
+    }
+}
+
+impl Output for LimitedOutput<'_> {
+    fn write_str(&mut self, text: &str) -> Result<(), RibboncurlsError> {
+        self.written = self.written.saturating_add(text.len());
+        if self.written > self.limit {
+            return Err(RibboncurlsError::StringSize { limit: self.limit });
+        }
+
+        self.out.write_str(text)
+    }
+}
+
+/// Prefixes each non-empty line written to `out` with `indent`, skipping the first line unless
+/// `include_first_line` is set. A line is only indented once its first character is written, and
+/// a `\r` at the start of a line is held back until it is known whether it starts a `\r\n`.
+pub struct IndentedOutput<'a> {
+    out: &'a mut dyn Output,
+    indent: &'a str,
+    at_line_start: bool,
+    pending_cr: bool,
+}
+
+impl<'a> IndentedOutput<'a> {
+    pub fn new(out: &'a mut dyn Output, indent: &'a str, include_first_line: bool) -> Self {
+        Self {
+            out,
+            indent,
+            at_line_start: include_first_line,
+            pending_cr: false,
+        }
+    }
+
+    /// Writes a `\r` held back from the last write, which is a line of its own
+    pub fn finish(self) -> Result<(), RibboncurlsError> {
+        if self.pending_cr {
+            self.out.write_str(self.indent)?;
+            self.out.write_str("\r")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Output for IndentedOutput<'_> {
+    fn write_str(&mut self, text: &str) -> Result<(), RibboncurlsError> {
+        let mut text = text;
+        if std::mem::take(&mut self.pending_cr) {
+            if let Some(rest) = text.strip_prefix('\n') {
+                self.out.write_str("\r\n")?;
+                text = rest;
+            } else {
+                self.out.write_str(self.indent)?;
+                self.out.write_str("\r")?;
+                self.at_line_start = false;
+            }
+        }
+
+        while !text.is_empty() {
+            if self.at_line_start {
+                // Empty lines aren't indented
+                let newline = if text.starts_with('\n') {
+                    "\n"
+                } else if text.starts_with("\r\n") {
+                    "\r\n"
+                } else if text == "\r" {
+                    self.pending_cr = true;
+
+                    return Ok(());
+                } else {
+                    self.out.write_str(self.indent)?;
+                    self.at_line_start = false;
+
+                    continue;
+                };
+                self.out.write_str(newline)?;
+                text = &text[newline.len()..];
+            } else if let Some(index) = text.find('\n') {
+                self.out.write_str(&text[..=index])?;
+                self.at_line_start = true;
+                text = &text[index + 1..];
+            } else {
+                self.out.write_str(text)?;
+                text = "";
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Escapes everything written to `out` a piece at a time, for output rendered from a template
+/// rather than a single value
+pub struct EscapedOutput<'a> {
+    out: &'a mut dyn Output,
+    escaper: &'a dyn Escaper,
+}
+
+impl<'a> EscapedOutput<'a> {
+    pub fn new(out: &'a mut dyn Output, escaper: &'a dyn Escaper) -> Self {
+        Self { out, escaper }
+    }
+}
+
+impl Output for EscapedOutput<'_> {
+    fn write_str(&mut self, text: &str) -> Result<(), RibboncurlsError> {
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        self.out.write_str(&self.escaper.escape(text))
+    }
+}
+
+/// Writes to `out`, recording whether anything was written
+pub struct TrackedOutput<'a> {
+    out: &'a mut dyn Output,
+    pub written: bool,
+}
+
+impl<'a> TrackedOutput<'a> {
+    pub fn new(out: &'a mut dyn Output) -> Self {
+        Self {
+            out,
+            written: false,
+        }
+    }
+}
+
+impl Output for TrackedOutput<'_> {
+    fn write_str(&mut self, text: &str) -> Result<(), RibboncurlsError> {
+        self.written |= !text.is_empty();
+
+        self.out.write_str(text)
+    }
+}
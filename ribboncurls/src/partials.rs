@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::hash::BuildHasher;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::{
+    syntax_tree::{create_syntax_tree, SyntaxItem},
+    token::tokenize,
+    Delimiters, Limits, RibboncurlsError, SyntaxCtx, TokenCtx,
+};
+
+/// Finds partial templates by name. A loader is asked for each partial the first time it is
+/// rendered, so partials can be read lazily from anywhere.
+///
+/// A `HashMap` of partial names to templates is a loader, and [`DirectoryLoader`] reads partials
+/// from files.
+pub trait PartialLoader: Send + Sync {
+    /// Returns the template for the `name` partial, or `None` if there is no such partial.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RibboncurlsError`] if the partial exists but cannot be loaded.
+    fn load(&self, name: &str) -> Result<Option<String>, RibboncurlsError>;
+}
+
+impl<S> PartialLoader for HashMap<String, String, S>
+where
+    S: BuildHasher + Send + Sync,
+{
+    fn load(&self, name: &str) -> Result<Option<String>, RibboncurlsError> {
+        Ok(self.get(name).cloned())
+    }
+}
+
+/// Shares one loader between several templates
+impl<L> PartialLoader for Arc<L>
+where
+    L: PartialLoader + ?Sized,
+{
+    fn load(&self, name: &str) -> Result<Option<String>, RibboncurlsError> {
+        (**self).load(name)
+    }
+}
+
+/// Loads partials from template files in a directory, where the partial name is the file path
+/// relative to the directory without the extension. `{{> colors/base16}}` loads
+/// `colors/base16.mustache`.
+///
+/// ```rust
+/// # let dir = std::env::temp_dir().join("ribboncurls-directory-loader-doc");
+/// # std::fs::create_dir_all(dir.join("colors")).unwrap();
+/// # std::fs::write(dir.join("colors/background.mustache"), "#{{base00}}").unwrap();
+/// let template = ribboncurls::Template::compile("{{> colors/background}}")?
+///     .with_partial_loader(ribboncurls::DirectoryLoader::new(&dir));
+///
+/// assert_eq!(template.render("base00: 181818")?, "#181818");
+/// # Ok::<(), ribboncurls::RibboncurlsError>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct DirectoryLoader {
+    root: PathBuf,
+    extension: String,
+}
+
+impl DirectoryLoader {
+    /// Loads partials from `root` with the `mustache` file extension
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            extension: "mustache".to_string(),
+        }
+    }
+
+    /// Sets the file extension of the partial files. An empty extension uses the partial name as
+    /// the file name.
+    #[must_use]
+    pub fn with_extension(mut self, extension: &str) -> Self {
+        self.extension = extension.to_string();
+
+        self
+    }
+}
+
+impl PartialLoader for DirectoryLoader {
+    fn load(&self, name: &str) -> Result<Option<String>, RibboncurlsError> {
+        // Only plain names are loaded so that a partial can't be read from outside `root`
+        let name_path = Path::new(name);
+        if !name_path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Ok(None);
+        }
+
+        let path = if self.extension.is_empty() {
+            self.root.join(name_path)
+        } else {
+            self.root.join(format!("{name}.{}", self.extension))
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(partial) => Ok(Some(partial)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(source) => Err(RibboncurlsError::PartialLoad {
+                name: name.to_string(),
+                source,
+            }),
+        }
+    }
+}
+
+/// The partials available while rendering. Each partial is loaded and parsed the first time it is
+/// rendered and the syntax tree is kept for later renders.
+pub struct Partials {
+    loader: Box<dyn PartialLoader>,
+    delimiters: Delimiters,
+    max_section_depth: usize,
+    syntax_trees: Mutex<HashMap<String, Option<Arc<Vec<SyntaxItem>>>>>,
+}
+
+impl Default for Partials {
+    fn default() -> Self {
+        Self::new(HashMap::new())
+    }
+}
+
+impl fmt::Debug for Partials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let syntax_trees = self
+            .syntax_trees
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        f.debug_struct("Partials")
+            .field("loaded", &syntax_trees.keys())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Partials {
+    pub fn new(loader: impl PartialLoader + 'static) -> Self {
+        Self {
+            loader: Box::new(loader),
+            delimiters: Delimiters::default(),
+            max_section_depth: Limits::default().max_section_depth(),
+            syntax_trees: Mutex::default(),
+        }
+    }
+
+    /// Replaces the loader, keeping the delimiters
+    pub fn set_loader(&mut self, loader: impl PartialLoader + 'static) {
+        self.loader = Box::new(loader);
+        self.clear_syntax_trees();
+    }
+
+    /// Sets the delimiters that partials start with
+    pub fn set_delimiters(&mut self, delimiters: Delimiters) {
+        self.delimiters = delimiters;
+        self.clear_syntax_trees();
+    }
+
+    /// Sets how deep sections can be nested in partials
+    pub fn set_max_section_depth(&mut self, max_section_depth: usize) {
+        self.max_section_depth = max_section_depth;
+        self.clear_syntax_trees();
+    }
+
+    /// Forgets the partials parsed so far, which were parsed with another loader or delimiters
+    fn clear_syntax_trees(&mut self) {
+        self.syntax_trees
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    /// Returns the syntax tree for the `name` partial, or `None` if there is no such partial
+    pub fn get(&self, name: &str) -> Result<Option<Arc<Vec<SyntaxItem>>>, RibboncurlsError> {
+        // A panic while holding the lock can't leave the cache half-written, so a poisoned lock
+        // is still safe to use
+        if let Some(syntax_tree) = self
+            .syntax_trees
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(name)
+        {
+            return Ok(syntax_tree.clone());
+        }
+
+        // Missing partials are cached too so that the loader isn't asked again
+        let syntax_tree = match self.loader.load(name)? {
+            Some(partial) => {
+                let mut token_ctx = TokenCtx::new(
+                    &self.delimiters,
+                    Some(name.to_string()),
+                    self.max_section_depth,
+                );
+                let tokens = tokenize(&partial, &mut token_ctx)?;
+                let syntax_ctx = SyntaxCtx { is_root: false };
+                let syntax_tree = create_syntax_tree(&tokens, &syntax_ctx)?;
+
+                Some(Arc::new(syntax_tree))
+            }
+            None => None,
+        };
+
+        self.syntax_trees
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(name.to_string(), syntax_tree.clone());
+
+        Ok(syntax_tree)
+    }
+}
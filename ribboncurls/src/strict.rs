@@ -0,0 +1,33 @@
+use std::collections::HashSet;
+
+/// Settings for strict rendering, where missing data and partials are errors instead of rendering
+/// nothing. Used with [`Template::with_strict_mode`](crate::Template::with_strict_mode).
+///
+/// Inverted sections never fail, since checking for missing data is what they are for.
+#[derive(Clone, Debug, Default)]
+pub struct StrictMode {
+    allowed_missing: HashSet<String>,
+}
+
+impl StrictMode {
+    /// Strict mode where nothing is allowed to be missing
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows the variable, section or partial called `name` to be missing. `name` is the name as
+    /// written in the tag, like `scheme.author`.
+    #[must_use]
+    pub fn allow_missing(mut self, name: &str) -> Self {
+        self.allowed_missing.insert(name.to_string());
+
+        self
+    }
+
+    /// Returns whether `name` is allowed to be missing
+    #[must_use]
+    pub fn allows_missing(&self, name: &str) -> bool {
+        self.allowed_missing.contains(name)
+    }
+}
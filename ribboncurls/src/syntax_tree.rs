@@ -5,24 +5,31 @@ use regex::Regex;
 use self::utils::cleanup_syntax_item_text_newline_and_spacing;
 
 use super::RibboncurlsError;
+#[cfg(feature = "filters")]
+use crate::filter::FilterCall;
 use crate::{
-    token::Token,
-    utils::{get_next_item, get_prev_item, get_regex_for_newline, NewlineRegex},
+    location::Location,
+    token::{SectionSource, Token},
+    utils::{get_next_item, get_prev_item, get_regex_for_newline, split_lines, NewlineRegex},
     SyntaxCtx,
 };
 
 #[derive(Clone, Debug)]
 pub enum SyntaxItem {
     Text(String),
-    Variable(String),
-    EscapedVariable(String),
+    Variable(VariableTag, Location),
+    EscapedVariable(VariableTag, Location),
     Delimiter {
         is_standalone: bool,
     },
     Partial {
-        indent: u8,
+        /// The whitespace before a standalone partial tag, added to each line of the partial
+        indent: String,
         is_standalone: bool,
         name: String,
+        /// `{{>*name}}`, where `name` is looked up in the data to get the partial name
+        is_dynamic: bool,
+        location: Location,
     },
     Comment {
         is_standalone: bool,
@@ -30,12 +37,67 @@ pub enum SyntaxItem {
     Section {
         name: String,
         is_inverted: bool,
-        items: Vec<SyntaxItem>,
+        items: Vec<Self>,
         open_is_standalone: bool,
         closed_is_standalone: bool,
+        source: SectionSource,
+        location: Location,
+    },
+    /// `{{<name}}...{{/name}}`, renders the `name` partial with `items` overriding its blocks
+    Parent {
+        name: String,
+        items: Vec<Self>,
+        indent: String,
+        is_standalone: bool,
+        location: Location,
+    },
+    /// `{{$name}}...{{/name}}`, renders `items` unless a parent overrides the block
+    Block {
+        name: String,
+        items: Vec<Self>,
+        indent: String,
+        open_is_standalone: bool,
+        closed_is_standalone: bool,
+        location: Location,
     },
 }
 
+/// The variable name in a variable tag, and the filters applied to its value
+#[derive(Clone, Debug)]
+pub struct VariableTag {
+    pub name: String,
+    #[cfg(feature = "filters")]
+    pub filters: Vec<FilterCall>,
+}
+
+impl VariableTag {
+    /// Parses the `content` of the variable tag at `location`, the variable name followed by its
+    /// filters
+    #[cfg(feature = "filters")]
+    pub fn parse(content: &str, location: &Location) -> Result<Self, RibboncurlsError> {
+        let (name, filters) =
+            crate::filter::parse(content).map_err(|message| RibboncurlsError::BadFilter {
+                tag: content.to_string(),
+                message,
+                location: location.clone(),
+            })?;
+
+        Ok(Self {
+            name: name.to_string(),
+            filters,
+        })
+    }
+
+    /// Parses the `content` of a variable tag, which is only the variable name without the
+    /// `filters` feature
+    #[cfg(not(feature = "filters"))]
+    pub fn parse(content: &str) -> Self {
+        Self {
+            name: content.to_string(),
+        }
+    }
+}
+
 #[allow(clippy::too_many_lines)]
 pub fn create_syntax_tree(
     tokens: &[Token],
@@ -43,22 +105,11 @@ pub fn create_syntax_tree(
 ) -> Result<Vec<SyntaxItem>, RibboncurlsError> {
     let mut syntax_tree: Vec<SyntaxItem> = Vec::new();
     let mut section_stack: Vec<SyntaxItem> = vec![];
-    let re_before_text = get_regex_for_newline(
-        NewlineRegex::EndsWithNewlineFollowedByWhitespace,
-        ctx.newline,
-    );
-    let re_after_text = get_regex_for_newline(
-        NewlineRegex::StartsWithNewlineFollowedByWhitespace,
-        ctx.newline,
-    );
-    // If SyntaxItem::Text whitespace matches it must be index == 0 since all text should start
-    // with newline str
-    let re_whitespace = Regex::new(r"^[ \t]*\z").expect("Unable to get regex");
 
     for (index, token) in tokens.iter().enumerate() {
         match token {
-            Token::Text(content) => {
-                let lines: Vec<&str> = content.split(ctx.newline.as_str()).collect();
+            Token::Text(content, _) => {
+                let lines = split_lines(content);
                 if let Some((first_line, rest_of_lines)) = lines.split_first() {
                     // Emtpy if starts with newline
                     if !first_line.is_empty() {
@@ -70,28 +121,35 @@ pub fn create_syntax_tree(
                     }
 
                     for other_line in rest_of_lines {
-                        let new_content = format!("{}{other_line}", ctx.newline.as_str());
                         push_item(
                             &mut syntax_tree,
                             &mut section_stack,
-                            SyntaxItem::Text(new_content.clone()),
+                            SyntaxItem::Text((*other_line).to_string()),
                         );
                     }
                 }
             }
-            Token::Variable(content) => push_item(
-                &mut syntax_tree,
-                &mut section_stack,
-                SyntaxItem::Variable(content.clone()),
-            ),
-            Token::EscapedVariable(content) => push_item(
-                &mut syntax_tree,
-                &mut section_stack,
-                SyntaxItem::EscapedVariable(content.clone()),
-            ),
-            Token::Partial(name) => {
+            Token::Variable(content, location) | Token::EscapedVariable(content, location) => {
+                #[cfg(feature = "filters")]
+                let tag = VariableTag::parse(content, location)?;
+                #[cfg(not(feature = "filters"))]
+                let tag = VariableTag::parse(content);
+                let item = if matches!(token, Token::EscapedVariable(..)) {
+                    SyntaxItem::EscapedVariable(tag, location.clone())
+                } else {
+                    SyntaxItem::Variable(tag, location.clone())
+                };
+
+                push_item(&mut syntax_tree, &mut section_stack, item);
+            }
+            Token::Partial(name, location) | Token::DynamicPartial(name, location) => {
                 let is_standalone = get_is_standalone(tokens, index, ctx);
-                let indent = get_indent(&syntax_tree, &section_stack, ctx);
+                // Only standalone partials are indented, inline ones continue the current line
+                let indent = if is_standalone {
+                    get_indent(&syntax_tree, &section_stack)
+                } else {
+                    String::new()
+                };
 
                 push_item(
                     &mut syntax_tree,
@@ -100,10 +158,12 @@ pub fn create_syntax_tree(
                         name: name.clone(),
                         is_standalone,
                         indent,
+                        is_dynamic: matches!(token, Token::DynamicPartial(..)),
+                        location: location.clone(),
                     },
                 );
             }
-            Token::Delimiter => {
+            Token::Delimiter(..) => {
                 let is_standalone = get_is_standalone(tokens, index, ctx);
 
                 push_item(
@@ -112,7 +172,7 @@ pub fn create_syntax_tree(
                     SyntaxItem::Delimiter { is_standalone },
                 );
             }
-            Token::Comment => {
+            Token::Comment(..) => {
                 let is_standalone = get_is_standalone(tokens, index, ctx);
 
                 push_item(
@@ -121,75 +181,88 @@ pub fn create_syntax_tree(
                     SyntaxItem::Comment { is_standalone },
                 );
             }
-            Token::OpenSection(name) => {
-                let section = create_syntax_tree_open_section(
-                    tokens,
-                    name.clone(),
-                    index,
-                    &re_after_text,
-                    &re_before_text,
-                    &re_whitespace,
-                );
-
-                section_stack.push(section);
+            Token::OpenSection(name, source, location) => {
+                section_stack.push(SyntaxItem::Section {
+                    name: name.clone(),
+                    items: Vec::new(),
+                    is_inverted: false,
+                    open_is_standalone: get_open_is_standalone(tokens, index),
+                    closed_is_standalone: false,
+                    source: source.clone(),
+                    location: location.clone(),
+                });
             }
-            Token::OpenInvertedSection(name) => {
-                let mut open_is_standalone = false;
-
-                match (get_prev_item(tokens, index), get_next_item(tokens, index)) {
-                    (None, Some(Token::Text(after_text))) => {
-                        if re_after_text.is_match(after_text) {
-                            open_is_standalone = true;
-                        }
-                    }
-                    (Some(Token::Text(before_text)), None) => {
-                        if re_before_text.is_match(before_text)
-                            || re_whitespace.is_match(before_text)
-                        {
-                            open_is_standalone = true;
-                        }
-                    }
-                    (Some(Token::Text(before_text)), Some(Token::Text(after_text))) => {
-                        if (re_before_text.is_match(before_text)
-                            || re_whitespace.is_match(before_text))
-                            && re_after_text.is_match(after_text)
-                        {
-                            open_is_standalone = true;
-                        }
-                    }
-                    _ => {}
-                }
-
+            Token::OpenInvertedSection(name, location) => {
                 section_stack.push(SyntaxItem::Section {
                     name: name.clone(),
                     items: Vec::new(),
                     is_inverted: true,
-                    open_is_standalone,
+                    open_is_standalone: get_open_is_standalone(tokens, index),
                     closed_is_standalone: false,
+                    source: SectionSource::default(),
+                    location: location.clone(),
                 });
             }
-            Token::CloseSection(_) => {
-                if let Some(SyntaxItem::Section {
-                    name,
-                    is_inverted,
-                    items,
-                    open_is_standalone,
-                    closed_is_standalone: _,
-                }) = section_stack.pop()
-                {
-                    let section = create_syntax_tree_close_section(
-                        tokens,
-                        items,
-                        name,
-                        index,
-                        is_inverted,
-                        open_is_standalone,
-                        &re_after_text,
-                        &re_before_text,
-                    );
-                    push_item(&mut syntax_tree, &mut section_stack, section);
+            Token::OpenParent(name, location) => {
+                let indent = get_indent(&syntax_tree, &section_stack);
+
+                section_stack.push(SyntaxItem::Parent {
+                    name: name.clone(),
+                    items: Vec::new(),
+                    indent,
+                    // Only the open tag is known at this point, the close tag is checked later
+                    is_standalone: get_is_line_start(tokens, index, ctx),
+                    location: location.clone(),
+                });
+            }
+            Token::OpenBlock(name, location) => {
+                section_stack.push(SyntaxItem::Block {
+                    name: name.clone(),
+                    items: Vec::new(),
+                    indent: get_line_indent(tokens, index),
+                    open_is_standalone: get_is_line_start(tokens, index, ctx)
+                        && get_is_line_end(tokens, index),
+                    closed_is_standalone: false,
+                    location: location.clone(),
+                });
+            }
+            Token::CloseParent(_) => {
+                if let Some(mut parent) = section_stack.pop() {
+                    if let SyntaxItem::Parent { is_standalone, .. } = &mut parent {
+                        *is_standalone = *is_standalone && get_is_line_end(tokens, index);
+                    }
+                    push_item(&mut syntax_tree, &mut section_stack, parent);
                 }
             }
+            Token::CloseSection(name, location) => match section_stack.pop() {
+                Some(mut block @ SyntaxItem::Block { .. }) => {
+                    if let SyntaxItem::Block {
+                        closed_is_standalone,
+                        ..
+                    } = &mut block
+                    {
+                        *closed_is_standalone =
+                            get_is_line_start(tokens, index, ctx) && get_is_line_end(tokens, index);
+                    }
+                    push_item(&mut syntax_tree, &mut section_stack, block);
+                }
+                Some(mut section) => {
+                    if let SyntaxItem::Section {
+                        closed_is_standalone,
+                        ..
+                    } = &mut section
+                    {
+                        *closed_is_standalone = get_close_is_standalone(tokens, index);
+                    }
+                    push_item(&mut syntax_tree, &mut section_stack, section);
+                }
+                None => {
+                    return Err(RibboncurlsError::UnexpectedCloseTag {
+                        name: name.clone(),
+                        location: location.clone(),
+                    })
+                }
+            },
         }
     }
 
@@ -198,136 +271,65 @@ pub fn create_syntax_tree(
     Ok(syntax_tree)
 }
 
-pub fn create_syntax_tree_open_section(
-    tokens: &[Token],
-    name: String,
-    index: usize,
-    re_after_text: &Regex,
-    re_before_text: &Regex,
-    re_whitespace: &Regex,
-) -> SyntaxItem {
-    // Set standalone if applicable
-    let mut open_is_standalone = false;
+/// Whether the section open tag at `index` is alone on its line
+pub fn get_open_is_standalone(tokens: &[Token], index: usize) -> bool {
+    let re_before_text = get_regex_for_newline(NewlineRegex::EndsWithNewlineFollowedByWhitespace);
+    let re_after_text = get_regex_for_newline(NewlineRegex::StartsWithNewlineFollowedByWhitespace);
+    // If the text before is only whitespace, the tag must be at the start of the template since
+    // all other text starts with a newline
+    let re_whitespace = Regex::new(r"^[ \t]*\z").expect("Unable to get regex");
 
     match (get_prev_item(tokens, index), get_next_item(tokens, index)) {
-        (None, Some(Token::Text(after_text))) => {
-            if re_after_text.is_match(after_text) {
-                open_is_standalone = true;
-            }
-        }
-        (Some(Token::Text(before_text)), None) => {
-            if re_before_text.is_match(before_text) || re_whitespace.is_match(before_text) {
-                open_is_standalone = true;
-            }
+        (None, Some(Token::Text(after_text, _))) => re_after_text.is_match(after_text),
+        (Some(Token::Text(before_text, _)), None) => {
+            re_before_text.is_match(before_text) || re_whitespace.is_match(before_text)
         }
-        (Some(Token::Text(before_text)), Some(Token::Text(after_text))) => {
-            if (re_before_text.is_match(before_text) || re_whitespace.is_match(before_text))
+        (Some(Token::Text(before_text, _)), Some(Token::Text(after_text, _))) => {
+            (re_before_text.is_match(before_text) || re_whitespace.is_match(before_text))
                 && re_after_text.is_match(after_text)
-            {
-                open_is_standalone = true;
-            }
         }
-        _ => {}
-    }
-
-    SyntaxItem::Section {
-        name,
-        items: Vec::new(),
-        is_inverted: false,
-        open_is_standalone,
-        closed_is_standalone: false,
+        _ => false,
     }
 }
 
-#[allow(clippy::too_many_arguments)]
-pub fn create_syntax_tree_close_section(
-    tokens: &[Token],
-    items: Vec<SyntaxItem>,
-    name: String,
-    index: usize,
-    is_inverted: bool,
-    open_is_standalone: bool,
-    re_after_text: &Regex,
-    re_before_text: &Regex,
-) -> SyntaxItem {
-    let mut closed_is_standalone = false;
+/// Whether the section close tag at `index` is alone on its line
+pub fn get_close_is_standalone(tokens: &[Token], index: usize) -> bool {
+    let re_before_text = get_regex_for_newline(NewlineRegex::EndsWithNewlineFollowedByWhitespace);
+    let re_after_text = get_regex_for_newline(NewlineRegex::StartsWithNewlineFollowedByWhitespace);
+
     match (get_prev_item(tokens, index), get_next_item(tokens, index)) {
-        (None, Some(Token::Text(after_text))) => {
-            if re_after_text.is_match(after_text) {
-                closed_is_standalone = true;
-            }
-        }
-        (Some(Token::Text(before_text)), None) => {
-            if re_before_text.is_match(before_text) {
-                closed_is_standalone = true;
-            }
-        }
-        (Some(Token::Text(before_text)), Some(Token::Text(after_text))) => {
-            if re_before_text.is_match(before_text) && re_after_text.is_match(after_text) {
-                closed_is_standalone = true;
-            }
+        (None, Some(Token::Text(after_text, _))) => re_after_text.is_match(after_text),
+        (Some(Token::Text(before_text, _)), None) => re_before_text.is_match(before_text),
+        (Some(Token::Text(before_text, _)), Some(Token::Text(after_text, _))) => {
+            re_before_text.is_match(before_text) && re_after_text.is_match(after_text)
         }
-        _ => {}
-    }
-
-    SyntaxItem::Section {
-        name,
-        is_inverted,
-        items,
-        open_is_standalone,
-        closed_is_standalone,
+        _ => false,
     }
 }
 
-fn get_indent(syntax_tree: &[SyntaxItem], section_stack: &[SyntaxItem], ctx: &SyntaxCtx) -> u8 {
-    let re_starts_with_newline_followed_by_whitespace = get_regex_for_newline(
-        NewlineRegex::StartsWithNewlineFollowedByWhitespace,
-        ctx.newline,
-    );
-    let mut indent: u8 = 0;
-
-    if let Some(SyntaxItem::Section { items, .. }) = section_stack.last() {
-        let last_text_item = items.iter().rfind(|item| {
-            if let SyntaxItem::Text(_) = item {
-                return true;
-            }
-            false
-        });
-
-        if let Some(SyntaxItem::Text(text)) = last_text_item {
-            let tmp_indent = re_starts_with_newline_followed_by_whitespace
-                .find(text)
-                .map_or(0, |m| m.as_str().len());
-
-            if let Ok(tmp_indent_u8) = u8::try_from(tmp_indent) {
-                if tmp_indent_u8 > 1 {
-                    indent = tmp_indent_u8 - 1;
-                }
-            }
-        }
-    }
-
-    // Find the last newline and determine indent
-    syntax_tree.iter().rfind(|item| {
-        if let SyntaxItem::Text(text) = item {
-            if text.starts_with('\n') {
-                let tmp_indent = re_starts_with_newline_followed_by_whitespace
-                    .find(text)
-                    .map_or(0, |m| m.as_str().len());
-
-                if let Ok(tmp_indent_u8) = u8::try_from(tmp_indent) {
-                    if tmp_indent_u8 > 1 {
-                        indent = tmp_indent_u8 - 1;
-                    }
-                }
-
-                return true;
-            }
-        }
-        false
+/// The whitespace after the newline of the last line so far, in the innermost open section that
+/// has one or else in `syntax_tree`, which is the indentation of a tag on that line
+fn get_indent(syntax_tree: &[SyntaxItem], section_stack: &[SyntaxItem]) -> String {
+    let re_starts_with_newline_followed_by_whitespace =
+        get_regex_for_newline(NewlineRegex::StartsWithNewlineFollowedByWhitespace);
+    let open_items = section_stack.iter().rev().filter_map(|item| match item {
+        SyntaxItem::Section { items, .. }
+        | SyntaxItem::Parent { items, .. }
+        | SyntaxItem::Block { items, .. } => Some(items.as_slice()),
+        _ => None,
     });
 
-    indent
+    open_items
+        .chain([syntax_tree])
+        .find_map(|items| {
+            items.iter().rev().find_map(|item| match item {
+                SyntaxItem::Text(text) => re_starts_with_newline_followed_by_whitespace
+                    .find(text)
+                    .map(|m| m.as_str().trim_start_matches(['\r', '\n']).to_string()),
+                _ => None,
+            })
+        })
+        .unwrap_or_default()
 }
 
 fn push_item(
@@ -335,47 +337,104 @@ fn push_item(
     section_stack: &mut [SyntaxItem],
     item: SyntaxItem,
 ) {
-    if let Some(SyntaxItem::Section { items, .. }) = section_stack.last_mut() {
-        items.push(item);
-    } else {
-        syntax_tree.push(item);
+    match section_stack.last_mut() {
+        Some(SyntaxItem::Section { items, .. } | SyntaxItem::Block { items, .. }) => {
+            items.push(item);
+        }
+        // Anything other than blocks inside a parent is ignored
+        Some(SyntaxItem::Parent { items, .. }) => {
+            if let SyntaxItem::Block { .. } = item {
+                items.push(item);
+            }
+        }
+        _ => syntax_tree.push(item),
     }
 }
 
-fn get_is_standalone(tokens: &[Token], index: usize, ctx: &SyntaxCtx) -> bool {
-    let re_before_text = get_regex_for_newline(
-        NewlineRegex::EndsWithNewlineFollowedByWhitespace,
-        ctx.newline,
-    );
-    let re_after_text = get_regex_for_newline(
-        NewlineRegex::StartsWithNewlineFollowedByWhitespace,
-        ctx.newline,
-    );
+pub fn get_is_standalone(tokens: &[Token], index: usize, ctx: &SyntaxCtx) -> bool {
+    let re_before_text = get_regex_for_newline(NewlineRegex::EndsWithNewlineFollowedByWhitespace);
+    let re_after_text = get_regex_for_newline(NewlineRegex::StartsWithNewlineFollowedByWhitespace);
     let re_whitespace = Regex::new(r"^[ \t]*\z").expect("Unable to get regex");
 
     match (get_prev_item(tokens, index), get_next_item(tokens, index)) {
-        (None, Some(Token::Text(after_text))) => {
-            if re_after_text.is_match(after_text) {
-                return true;
-            }
+        (None, Some(Token::Text(after_text, _))) if re_after_text.is_match(after_text) => {
+            return true;
         }
-        (Some(Token::Text(before_text)), None) => {
-            if re_before_text.is_match(before_text)
-                || (index == 1 && ctx.is_root && re_whitespace.is_match(before_text))
-            {
-                return true;
-            }
+        (Some(Token::Text(before_text, _)), None)
+            if (re_before_text.is_match(before_text)
+                || (index == 1 && ctx.is_root && re_whitespace.is_match(before_text))) =>
+        {
+            return true;
         }
-        (Some(Token::Text(before_text)), Some(Token::Text(after_text))) => {
+        (Some(Token::Text(before_text, _)), Some(Token::Text(after_text, _)))
             if (re_before_text.is_match(before_text)
                 || (index == 1 && ctx.is_root && re_whitespace.is_match(before_text)))
-                && re_after_text.is_match(after_text)
-            {
-                return true;
-            }
+                && re_after_text.is_match(after_text) =>
+        {
+            return true;
         }
         _ => {}
     }
 
     false
 }
+
+/// Whether only whitespace comes before the tag at `index` on its line. Parent open tags render
+/// nothing, so they are skipped over, which lets a block open tag share a line with its parent.
+pub fn get_is_line_start(tokens: &[Token], index: usize, ctx: &SyntaxCtx) -> bool {
+    let re_before_text = get_regex_for_newline(NewlineRegex::EndsWithNewlineFollowedByWhitespace);
+    let re_whitespace = Regex::new(r"^[ \t]*\z").expect("Unable to get regex");
+    let mut index = index;
+    while let Some(Token::OpenParent(..)) = get_prev_item(tokens, index) {
+        index -= 1;
+    }
+
+    match get_prev_item(tokens, index) {
+        None => true,
+        Some(Token::Text(before_text, _)) => {
+            re_before_text.is_match(before_text)
+                || (index == 1 && ctx.is_root && re_whitespace.is_match(before_text))
+        }
+        _ => false,
+    }
+}
+
+/// Whether the tag at `index` is followed by a newline or the end of the template. Parent close
+/// tags render nothing, so they are skipped over.
+pub fn get_is_line_end(tokens: &[Token], index: usize) -> bool {
+    let re_after_text = get_regex_for_newline(NewlineRegex::StartsWithNewlineFollowedByWhitespace);
+    let mut index = index;
+    while matches!(get_next_item(tokens, index), Some(Token::CloseParent(_))) {
+        index += 1;
+    }
+
+    match get_next_item(tokens, index) {
+        None => true,
+        Some(Token::Text(after_text, _)) => re_after_text.is_match(after_text),
+        _ => false,
+    }
+}
+
+/// The whitespace before the tag at `index` when nothing else precedes it on its line
+fn get_line_indent(tokens: &[Token], index: usize) -> String {
+    let Some(Token::Text(before_text, _)) = get_prev_item(tokens, index) else {
+        return String::new();
+    };
+    let line = before_text.rsplit('\n').next().unwrap_or_default();
+
+    if (index == 1 || before_text.contains('\n')) && line.chars().all(|c| c == ' ' || c == '\t') {
+        line.to_string()
+    } else {
+        String::new()
+    }
+}
+
+/// The leading whitespace of the first line of `items`, when there is any
+pub fn get_text_indent(items: &[SyntaxItem]) -> Option<&str> {
+    let Some(SyntaxItem::Text(text)) = items.first() else {
+        return None;
+    };
+    let indent = &text[..text.len() - text.trim_start_matches([' ', '\t']).len()];
+
+    Some(indent).filter(|indent| !indent.is_empty())
+}
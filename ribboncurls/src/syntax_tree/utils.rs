@@ -1,87 +1,111 @@
 use regex::Regex;
 
-use super::{RibboncurlsError, SyntaxCtx, SyntaxItem};
-use crate::utils::{get_next_item, get_prev_item, get_regex_for_newline, NewlineRegex};
+use super::{get_text_indent, RibboncurlsError, SyntaxCtx, SyntaxItem};
+use crate::utils::{get_regex_for_newline, strip_newline, NewlineRegex};
 
 #[allow(clippy::too_many_lines)]
 pub fn cleanup_syntax_item_text_newline_and_spacing(
     syntax_tree: &mut Vec<SyntaxItem>,
     ctx: &SyntaxCtx,
 ) -> Result<(), RibboncurlsError> {
-    let syntax_tree_clone = syntax_tree.clone();
+    // Blocks inside a parent are cleaned up on their own since the text surrounding them is never
+    // rendered
+    for node in syntax_tree.iter_mut() {
+        if let SyntaxItem::Parent { items, .. } = node {
+            let parent_ctx = SyntaxCtx { is_root: false };
+            cleanup_syntax_item_text_newline_and_spacing(items, &parent_ctx)?;
+
+            for item in items.iter_mut() {
+                dedent_block_definition(item);
+            }
+        }
+    }
+
     let mut syntax_text_items_to_remove: Vec<usize> = vec![];
     let mut syntax_item_newlines_to_remove: Vec<usize> = vec![];
     let mut syntax_items_remove_ending_whitespace: Vec<usize> = vec![];
     let re_before_text_last_syntax_item = Regex::new(r"[ \t]*\z").expect("Unable to get regex");
-    let re_newline = get_regex_for_newline(NewlineRegex::StartsWithNewline, ctx.newline);
+    let re_newline = get_regex_for_newline(NewlineRegex::StartsWithNewline);
     let re_empty_line = Regex::new(r"^\s*\z").expect("Unable to get regex");
     let re_ending_whitespace = Regex::new(r"[ \t]*\z").expect("Unable to get regex");
     let re_whitespace = Regex::new(r"^[ \t]*\z").expect("Unable to get regex");
 
-    for (index, node) in syntax_tree.iter_mut().enumerate() {
+    for index in 0..syntax_tree.len() {
+        // The items either side are only read, so they are borrowed apart from the item being
+        // cleaned up rather than cloning the tree
+        let (before, rest) = syntax_tree.split_at_mut(index);
+        let Some((node, after)) = rest.split_first_mut() else {
+            break;
+        };
+        let prev_item = before.last();
+        let next_item = after.first();
+
         match node {
-            SyntaxItem::Delimiter { is_standalone } | SyntaxItem::Comment { is_standalone } => {
-                if *is_standalone {
-                    if let Some(SyntaxItem::Text(text)) = get_prev_item(&syntax_tree_clone, index) {
-                        if re_ending_whitespace.is_match(text) {
-                            syntax_items_remove_ending_whitespace.push(index - 1);
-                        }
+            SyntaxItem::Delimiter { is_standalone } | SyntaxItem::Comment { is_standalone }
+                if *is_standalone =>
+            {
+                if let Some(SyntaxItem::Text(text)) = prev_item {
+                    if re_ending_whitespace.is_match(text) {
+                        syntax_items_remove_ending_whitespace.push(index - 1);
                     }
+                }
 
-                    if let Some(SyntaxItem::Text(text)) = get_next_item(&syntax_tree_clone, index) {
-                        if re_before_text_last_syntax_item.is_match(text) {
-                            syntax_item_newlines_to_remove.push(index + 1);
-                        }
+                if let Some(SyntaxItem::Text(text)) = next_item {
+                    if re_before_text_last_syntax_item.is_match(text) {
+                        syntax_item_newlines_to_remove.push(index + 1);
                     }
                 }
             }
             SyntaxItem::Partial {
                 indent,
                 is_standalone,
-                name: _,
-            } => {
-                if *is_standalone {
-                    if let Some(SyntaxItem::Text(text)) = get_prev_item(&syntax_tree_clone, index) {
-                        let prev_index = index - 1;
+                ..
+            }
+            | SyntaxItem::Parent {
+                indent,
+                is_standalone,
+                ..
+            } if *is_standalone => {
+                if let Some(SyntaxItem::Text(text)) = prev_item {
+                    let prev_index = index - 1;
 
-                        if prev_index == 0 && re_whitespace.is_match(text) {
-                            if let Ok(len_u8) = u8::try_from(text.len()) {
-                                *indent = len_u8;
-                            }
-                        }
+                    if prev_index == 0 && re_whitespace.is_match(text) {
+                        indent.clone_from(text);
+                    }
 
-                        if re_ending_whitespace.is_match(text) {
-                            syntax_items_remove_ending_whitespace.push(prev_index);
-                        }
+                    if re_ending_whitespace.is_match(text) {
+                        syntax_items_remove_ending_whitespace.push(prev_index);
                     }
+                }
 
-                    if let Some(SyntaxItem::Text(text)) = get_next_item(&syntax_tree_clone, index) {
-                        let next_index = index + 1;
+                if let Some(SyntaxItem::Text(text)) = next_item {
+                    let next_index = index + 1;
 
-                        if re_before_text_last_syntax_item.is_match(text) {
-                            syntax_item_newlines_to_remove.push(next_index);
-                        }
+                    if re_before_text_last_syntax_item.is_match(text) {
+                        syntax_item_newlines_to_remove.push(next_index);
                     }
                 }
             }
 
             SyntaxItem::Section {
-                name: _,
-                is_inverted: _,
                 items,
                 open_is_standalone,
                 closed_is_standalone,
+                ..
+            }
+            | SyntaxItem::Block {
+                items,
+                open_is_standalone,
+                closed_is_standalone,
+                ..
             } => {
-                let section_ctx = SyntaxCtx {
-                    newline: ctx.newline,
-                    is_root: false,
-                };
+                let section_ctx = SyntaxCtx { is_root: false };
                 cleanup_syntax_item_text_newline_and_spacing(items, &section_ctx)?;
 
                 // Strip the last SyntaxItem::Section.items item if it begins
                 // with a newline and only contains spaces afterwards
                 if *closed_is_standalone {
-                    if let Some(SyntaxItem::Text(text)) = get_next_item(&syntax_tree_clone, index) {
+                    if let Some(SyntaxItem::Text(text)) = next_item {
                         if re_before_text_last_syntax_item.is_match(text) {
                             syntax_item_newlines_to_remove.push(index + 1);
                         }
@@ -99,16 +123,12 @@ pub fn cleanup_syntax_item_text_newline_and_spacing(
                     // When the first SyntaxItem is a section, strip the leading newline and spaces
                     // within the SyntaxItem::Section.items
                     if ctx.is_root && index == 1 {
-                        if let Some(SyntaxItem::Text(text)) =
-                            get_prev_item(&syntax_tree_clone, index)
-                        {
+                        if let Some(SyntaxItem::Text(text)) = prev_item {
                             if re_empty_line.is_match(text) {
                                 syntax_text_items_to_remove.push(index - 1);
                             }
                         }
-                    } else if let Some(SyntaxItem::Text(text)) =
-                        get_prev_item(&syntax_tree_clone, index)
-                    {
+                    } else if let Some(SyntaxItem::Text(text)) = prev_item {
                         if re_ending_whitespace.is_match(text) {
                             syntax_items_remove_ending_whitespace.push(index - 1);
                         }
@@ -146,3 +166,40 @@ pub fn cleanup_syntax_item_text_newline_and_spacing(
 
     Ok(())
 }
+
+/// Removes the indentation of the first line of a standalone block definition from each of its
+/// lines, so that it can be indented to match wherever the block is expanded
+fn dedent_block_definition(block: &mut SyntaxItem) {
+    let SyntaxItem::Block {
+        items,
+        open_is_standalone: true,
+        ..
+    } = block
+    else {
+        return;
+    };
+    let Some(indent) = get_text_indent(items).map(str::len) else {
+        return;
+    };
+    for (index, item) in items.iter_mut().enumerate() {
+        if let SyntaxItem::Text(text) = item {
+            let (line_start, line) = if index == 0 {
+                ("", text.as_str())
+            } else if let Some((newline, line)) = strip_newline(text) {
+                (newline, line)
+            } else {
+                continue;
+            };
+            let dedented = line
+                .char_indices()
+                .take(indent)
+                .find(|(_, c)| *c != ' ' && *c != '\t')
+                .map_or_else(
+                    || line.get(indent..).unwrap_or_default(),
+                    |(char_index, _)| &line[char_index..],
+                );
+
+            *text = format!("{line_start}{dedented}");
+        }
+    }
+}
@@ -0,0 +1,503 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+
+use serde::Serialize;
+use serde_yaml::Value;
+
+#[cfg(feature = "filters")]
+use crate::Filters;
+use crate::{
+    introspect::collect_references,
+    output::{FmtOutput, IoOutput, LimitedOutput, NewlineOutput, Output},
+    partials::{PartialLoader, Partials},
+    render_syntax_tree,
+    syntax_tree::{create_syntax_tree, SyntaxItem},
+    token::tokenize,
+    DataFormat, Delimiters, Escaper, HtmlEscaper, Lambdas, Limits, LineEndings, MustacheFormatter,
+    Reference, RenderCtx, RibboncurlsError, StrictMode, SyntaxCtx, TokenCtx, ValueFormatter,
+};
+
+/// A parsed template that can be rendered any number of times.
+///
+/// The template is parsed once by [`Template::compile`] and partials are parsed the first time
+/// they are rendered, so rendering the same template against lots of data skips the parsing.
+/// A `Template` is `Send + Sync` and can be shared between threads.
+///
+/// ```rust
+/// let template = ribboncurls::Template::compile("{{> header}}{{name}}")?
+///     .with_partials("header: 'Scheme: '")?;
+///
+/// assert_eq!(template.render("name: Tinted")?, "Scheme: Tinted");
+/// assert_eq!(template.render("name: Default")?, "Scheme: Default");
+/// # Ok::<(), ribboncurls::RibboncurlsError>(())
+/// ```
+#[derive(Debug)]
+pub struct Template {
+    syntax_tree: Vec<SyntaxItem>,
+    partials: Partials,
+    lambdas: Lambdas,
+    #[cfg(feature = "filters")]
+    filters: Filters,
+    strict: Option<StrictMode>,
+    escaper: Box<dyn Escaper>,
+    formatter: Box<dyn ValueFormatter>,
+    line_endings: LineEndings,
+    limits: Limits,
+}
+
+impl Template {
+    /// Parses a template string.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RibboncurlsError`] if the template cannot be tokenized or parsed.
+    pub fn compile(template: &str) -> Result<Self, RibboncurlsError> {
+        Self::compile_with_delimiters(template, &Delimiters::default())
+    }
+
+    /// Parses a template string that starts with `delimiters` instead of `{{` and `}}`. Partials
+    /// keep the default delimiters unless they are set with [`Template::with_partial_delimiters`].
+    ///
+    /// ```rust
+    /// let delimiters = "<% %>".parse()?;
+    /// let template =
+    ///     ribboncurls::Template::compile_with_delimiters("{{ .Values }}: <% name %>", &delimiters)?;
+    ///
+    /// assert_eq!(template.render("name: Tinted")?, "{{ .Values }}: Tinted");
+    /// # Ok::<(), ribboncurls::RibboncurlsError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RibboncurlsError`] if the template cannot be tokenized or parsed.
+    pub fn compile_with_delimiters(
+        template: &str,
+        delimiters: &Delimiters,
+    ) -> Result<Self, RibboncurlsError> {
+        Self::compile_with_options(template, delimiters, Limits::default())
+    }
+
+    /// Parses a template string with `limits` instead of the default [`Limits`], which are also
+    /// used when it is rendered. Only the section depth limit applies to parsing, so this is only
+    /// needed to parse templates with sections nested more than 100 deep.
+    ///
+    /// ```rust
+    /// use ribboncurls::{Limits, RibboncurlsError, Template};
+    ///
+    /// let template = "{{#a}}{{#b}}{{/b}}{{/a}}";
+    ///
+    /// assert!(matches!(
+    ///     Template::compile_with_limits(template, Limits::new().section_depth(1)),
+    ///     Err(RibboncurlsError::SectionDepth { limit: 1, .. })
+    /// ));
+    /// assert!(Template::compile_with_limits(template, Limits::new().section_depth(2)).is_ok());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RibboncurlsError`] if the template cannot be tokenized or parsed.
+    pub fn compile_with_limits(template: &str, limits: Limits) -> Result<Self, RibboncurlsError> {
+        Self::compile_with_options(template, &Delimiters::default(), limits)
+    }
+
+    fn compile_with_options(
+        template: &str,
+        delimiters: &Delimiters,
+        limits: Limits,
+    ) -> Result<Self, RibboncurlsError> {
+        let mut token_ctx = TokenCtx::new(delimiters, None, limits.max_section_depth());
+        let tokens = tokenize(template, &mut token_ctx)?;
+        let syntax_ctx = SyntaxCtx { is_root: true };
+        let template = Self {
+            syntax_tree: create_syntax_tree(&tokens, &syntax_ctx)?,
+            partials: Partials::default(),
+            lambdas: Lambdas::default(),
+            #[cfg(feature = "filters")]
+            filters: Filters::default(),
+            strict: None,
+            escaper: Box::new(HtmlEscaper),
+            formatter: Box::new(MustacheFormatter),
+            line_endings: LineEndings::default(),
+            limits: Limits::default(),
+        };
+
+        Ok(template.with_limits(limits))
+    }
+
+    /// Sets the partials available to the template from a YAML mapping of partial names to
+    /// templates, replacing any partials set before.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RibboncurlsError`] if the partials are not a YAML mapping of strings.
+    pub fn with_partials(self, partials: &str) -> Result<Self, RibboncurlsError> {
+        self.with_partials_as(partials, DataFormat::Yaml)
+    }
+
+    /// Sets the partials available to the template from a mapping of partial names to templates
+    /// in the given format, replacing any partials set before.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RibboncurlsError`] if the partials are not a mapping of strings in `format`.
+    pub fn with_partials_as(
+        mut self,
+        partials: &str,
+        format: DataFormat,
+    ) -> Result<Self, RibboncurlsError> {
+        self.partials.set_loader(format.parse_partials(partials)?);
+
+        Ok(self)
+    }
+
+    /// Sets the partials available to the template from a map of partial names to templates,
+    /// replacing any partials set before.
+    #[must_use]
+    pub fn with_partial_map(self, partials: HashMap<String, String>) -> Self {
+        self.with_partial_loader(partials)
+    }
+
+    /// Sets the [`PartialLoader`] that partials are loaded from when they are first rendered,
+    /// replacing any partials set before.
+    #[must_use]
+    pub fn with_partial_loader(mut self, loader: impl PartialLoader + 'static) -> Self {
+        self.partials.set_loader(loader);
+
+        self
+    }
+
+    /// Sets the [`Delimiters`] that every partial starts with, which are `{{` and `}}` by default
+    /// whatever delimiters the template uses.
+    ///
+    /// ```rust
+    /// let template = ribboncurls::Template::compile("{{> values}}")?
+    ///     .with_partials("values: 'image: <% image %>:{{ .Chart.AppVersion }}'")?
+    ///     .with_partial_delimiters("<% %>".parse()?);
+    ///
+    /// assert_eq!(template.render("image: nginx")?, "image: nginx:{{ .Chart.AppVersion }}");
+    /// # Ok::<(), ribboncurls::RibboncurlsError>(())
+    /// ```
+    #[must_use]
+    pub fn with_partial_delimiters(mut self, delimiters: Delimiters) -> Self {
+        self.partials.set_delimiters(delimiters);
+
+        self
+    }
+
+    /// Sets the [`Lambdas`] available to the template, replacing any lambdas set before.
+    #[must_use]
+    pub fn with_lambdas(mut self, lambdas: Lambdas) -> Self {
+        self.lambdas = lambdas;
+
+        self
+    }
+
+    /// Sets the [`Filters`] available to variable tags, replacing the standard filters.
+    #[cfg(feature = "filters")]
+    #[must_use]
+    pub fn with_filters(mut self, filters: Filters) -> Self {
+        self.filters = filters;
+
+        self
+    }
+
+    /// Makes rendering fail with a [`RibboncurlsError::MissingData`] or
+    /// [`RibboncurlsError::MissingPartial`] error when a variable, section or partial can't be
+    /// found, instead of rendering nothing.
+    ///
+    /// ```rust
+    /// let template = ribboncurls::Template::compile("{{name}}{{subtitle}}")?
+    ///     .with_strict_mode(ribboncurls::StrictMode::new().allow_missing("subtitle"));
+    ///
+    /// assert_eq!(template.render("name: Tinted")?, "Tinted");
+    /// assert!(template.render("title: Tinted").is_err());
+    /// # Ok::<(), ribboncurls::RibboncurlsError>(())
+    /// ```
+    #[must_use]
+    pub fn with_strict_mode(mut self, strict: StrictMode) -> Self {
+        self.strict = Some(strict);
+
+        self
+    }
+
+    /// Sets the [`Escaper`] used for the values of `{{name}}` variable tags, replacing the
+    /// default [`HtmlEscaper`].
+    ///
+    /// ```rust
+    /// let template = ribboncurls::Template::compile(r#"{"name": "{{name}}"}"#)?
+    ///     .with_escaper(ribboncurls::JsonStringEscaper);
+    ///
+    /// assert_eq!(template.render(r#"name: 'Tinted "dark"'"#)?, r#"{"name": "Tinted \"dark\""}"#);
+    /// # Ok::<(), ribboncurls::RibboncurlsError>(())
+    /// ```
+    #[must_use]
+    pub fn with_escaper(mut self, escaper: impl Escaper + 'static) -> Self {
+        self.escaper = Box::new(escaper);
+
+        self
+    }
+
+    /// Sets the [`ValueFormatter`] that turns the values of variable tags into text, replacing the
+    /// default [`MustacheFormatter`].
+    ///
+    /// ```rust
+    /// let template = ribboncurls::Template::compile("{{fonts}}")?
+    ///     .with_formatter(ribboncurls::ConfigFormatter::new().separator(" "));
+    ///
+    /// assert_eq!(template.render("fonts: [Hack, Iosevka]")?, "Hack Iosevka");
+    /// # Ok::<(), ribboncurls::RibboncurlsError>(())
+    /// ```
+    #[must_use]
+    pub fn with_formatter(mut self, formatter: impl ValueFormatter + 'static) -> Self {
+        self.formatter = Box::new(formatter);
+
+        self
+    }
+
+    /// Sets the [`LineEndings`] of the rendered output, which by default keeps whatever the
+    /// template, partials and data use.
+    ///
+    /// ```rust
+    /// use ribboncurls::LineEndings;
+    ///
+    /// let template = ribboncurls::Template::compile("{{#dark}}\r\ndark\n{{/dark}}\r\n{{name}}\n")?
+    ///     .with_line_endings(LineEndings::Crlf);
+    ///
+    /// assert_eq!(template.render("{dark: true, name: Tinted}")?, "dark\r\nTinted\r\n");
+    /// # Ok::<(), ribboncurls::RibboncurlsError>(())
+    /// ```
+    #[must_use]
+    pub const fn with_line_endings(mut self, line_endings: LineEndings) -> Self {
+        self.line_endings = line_endings;
+
+        self
+    }
+
+    /// Sets the [`Limits`] on rendering, replacing the default limits. The section depth limit
+    /// applies to partials, which are parsed when they are rendered, but not to the template,
+    /// which is already parsed.
+    ///
+    /// ```rust
+    /// use ribboncurls::{Limits, RibboncurlsError};
+    ///
+    /// let template = ribboncurls::Template::compile("{{#colors}}{{.}} {{/colors}}")?
+    ///     .with_limits(Limits::new().iterations(2));
+    ///
+    /// assert_eq!(template.render("colors: [red, blue]")?, "red blue ");
+    /// assert!(matches!(
+    ///     template.render("colors: [red, green, blue]"),
+    ///     Err(RibboncurlsError::Iterations { limit: 2, .. })
+    /// ));
+    /// # Ok::<(), ribboncurls::RibboncurlsError>(())
+    /// ```
+    #[must_use]
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.partials
+            .set_max_section_depth(limits.max_section_depth());
+        self.limits = limits;
+
+        self
+    }
+
+    /// Loads and parses the `name` partial now instead of the first time it is rendered, so that
+    /// errors in it are found without rendering. Returns whether there is such a partial.
+    ///
+    /// ```rust
+    /// let template = ribboncurls::Template::compile("{{> header}}")?
+    ///     .with_partials("header: '{{#name}}'")?;
+    ///
+    /// assert!(template.preload_partial("header").is_err());
+    /// assert!(!template.preload_partial("footer")?);
+    /// # Ok::<(), ribboncurls::RibboncurlsError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RibboncurlsError`] if the partial cannot be loaded, tokenized or parsed.
+    pub fn preload_partial(&self, name: &str) -> Result<bool, RibboncurlsError> {
+        Ok(self.partials.get(name)?.is_some())
+    }
+
+    /// Lists every variable, section, inverted section, partial and parent that the template
+    /// refers to, in template order. Partials are listed by name and not looked into.
+    ///
+    /// ```rust
+    /// use ribboncurls::ReferenceKind;
+    ///
+    /// let template = ribboncurls::Template::compile("{{#palette}}{{base00}}{{/palette}}")?;
+    /// let references = template.references();
+    ///
+    /// assert_eq!(references[1].kind, ReferenceKind::Variable);
+    /// assert_eq!(references[1].name, "base00");
+    /// assert_eq!(references[1].path, ["palette"]);
+    /// # Ok::<(), ribboncurls::RibboncurlsError>(())
+    /// ```
+    #[must_use]
+    pub fn references(&self) -> Vec<Reference> {
+        let mut references = Vec::new();
+        collect_references(&self.syntax_tree, &mut Vec::new(), &mut references);
+
+        references
+    }
+
+    /// Renders the template using YAML data.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RibboncurlsError`] if a partial, or a string returned by a lambda, cannot be
+    /// tokenized or parsed.
+    pub fn render(&self, data: &str) -> Result<String, RibboncurlsError> {
+        self.render_with_lambdas(data_from_yaml(data), &self.lambdas)
+    }
+
+    /// Renders the template using any serializable data, such as a struct or a
+    /// [`serde_yaml::Value`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RibboncurlsError`] if the data cannot be serialized, or a partial or a string
+    /// returned by a lambda cannot be tokenized or parsed.
+    pub fn render_with<T>(&self, data: &T) -> Result<String, RibboncurlsError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.render_with_lambdas(serde_yaml::to_value(data)?, &self.lambdas)
+    }
+
+    /// Renders the template using data in the given format.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RibboncurlsError`] if the data isn't valid in `format`, or a partial or a
+    /// string returned by a lambda cannot be tokenized or parsed.
+    pub fn render_as(&self, data: &str, format: DataFormat) -> Result<String, RibboncurlsError> {
+        self.render_with_lambdas(format.parse(data)?, &self.lambdas)
+    }
+
+    /// Renders the template using YAML data, writing the output to `writer` as it is rendered.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RibboncurlsError`] if writing fails, or a partial or a string returned by a
+    /// lambda cannot be tokenized or parsed. Output rendered before the error has already been
+    /// written.
+    pub fn render_to<W>(&self, data: &str, writer: &mut W) -> Result<(), RibboncurlsError>
+    where
+        W: io::Write + ?Sized,
+    {
+        self.render_value_to(data_from_yaml(data), &self.lambdas, &mut IoOutput(writer))
+    }
+
+    /// Renders the template using any serializable data, writing the output to `writer` as it is
+    /// rendered.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RibboncurlsError`] if the data cannot be serialized, writing fails, or a
+    /// partial or a string returned by a lambda cannot be tokenized or parsed.
+    pub fn render_with_to<T, W>(&self, data: &T, writer: &mut W) -> Result<(), RibboncurlsError>
+    where
+        T: Serialize + ?Sized,
+        W: io::Write + ?Sized,
+    {
+        self.render_value_to(
+            serde_yaml::to_value(data)?,
+            &self.lambdas,
+            &mut IoOutput(writer),
+        )
+    }
+
+    /// Renders the template using YAML data, writing the output to a [`std::fmt::Write`] such as a
+    /// `String` or a `Formatter`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RibboncurlsError`] if writing fails, or a partial or a string returned by a
+    /// lambda cannot be tokenized or parsed.
+    pub fn render_fmt<W>(&self, data: &str, writer: &mut W) -> Result<(), RibboncurlsError>
+    where
+        W: fmt::Write + ?Sized,
+    {
+        self.render_value_to(data_from_yaml(data), &self.lambdas, &mut FmtOutput(writer))
+    }
+
+    /// Renders the template using any serializable data, writing the output to a
+    /// [`std::fmt::Write`] such as a `String` or a `Formatter`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RibboncurlsError`] if the data cannot be serialized, writing fails, or a
+    /// partial or a string returned by a lambda cannot be tokenized or parsed.
+    pub fn render_with_fmt<T, W>(&self, data: &T, writer: &mut W) -> Result<(), RibboncurlsError>
+    where
+        T: Serialize + ?Sized,
+        W: fmt::Write + ?Sized,
+    {
+        self.render_value_to(
+            serde_yaml::to_value(data)?,
+            &self.lambdas,
+            &mut FmtOutput(writer),
+        )
+    }
+
+    /// Renders the template with lambdas that are borrowed rather than set on the template
+    pub(crate) fn render_with_lambdas(
+        &self,
+        data: Value,
+        lambdas: &Lambdas,
+    ) -> Result<String, RibboncurlsError> {
+        let mut output = String::new();
+        self.render_value_to(data, lambdas, &mut output)?;
+
+        Ok(output)
+    }
+
+    fn render_value_to(
+        &self,
+        data: Value,
+        lambdas: &Lambdas,
+        out: &mut dyn Output,
+    ) -> Result<(), RibboncurlsError> {
+        let mut render_context = RenderCtx {
+            data_stack: vec![data],
+            partials: &self.partials,
+            lambdas,
+            #[cfg(feature = "filters")]
+            filters: &self.filters,
+            strict: self.strict.as_ref(),
+            escaper: self.escaper.as_ref(),
+            formatter: self.formatter.as_ref(),
+            blocks: HashMap::new(),
+            section_path: vec![],
+            limits: &self.limits,
+            partial_depth: 0,
+            section_depth: 0,
+            iterations: 0,
+            indent: String::new(),
+        };
+
+        // The size limit is checked after the line endings are changed, on the bytes written
+        let mut limited_output;
+        let out: &mut dyn Output = match self.limits.max_output_bytes() {
+            Some(limit) => {
+                limited_output = LimitedOutput::new(out, limit);
+                &mut limited_output
+            }
+            None => out,
+        };
+
+        let Some(newline) = self.line_endings.newline() else {
+            return render_syntax_tree(&self.syntax_tree, &mut render_context, out);
+        };
+        let mut out = NewlineOutput::new(out, newline);
+        render_syntax_tree(&self.syntax_tree, &mut render_context, &mut out)?;
+
+        out.finish()
+    }
+}
+
+/// YAML data that fails to parse is used as a plain string
+pub fn data_from_yaml(data: &str) -> Value {
+    serde_yaml::from_str(data).unwrap_or(Value::String(data.to_string()))
+}
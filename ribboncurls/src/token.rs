@@ -1,43 +1,91 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use super::delimiters::delimiter_problem;
+use super::location::LocationCursor;
+use super::Location;
 use super::RibboncurlsError;
 use super::TokenCtx;
 
 #[derive(Clone, Debug)]
 pub enum Token {
-    Text(String),
-    Variable(String),
-    EscapedVariable(String),
-    OpenSection(String),
-    CloseSection(String),
-    OpenInvertedSection(String),
-    Partial(String),
-    Comment,
-    Delimiter,
+    Text(String, Location),
+    Variable(String, Location),
+    EscapedVariable(String, Location),
+    OpenSection(String, SectionSource, Location),
+    CloseSection(String, Location),
+    OpenInvertedSection(String, Location),
+    OpenParent(String, Location),
+    CloseParent(Location),
+    OpenBlock(String, Location),
+    Partial(String, Location),
+    DynamicPartial(String, Location),
+    Comment(String, Location),
+    /// Sets the left and right delimiters
+    Delimiter(String, String, Location),
+}
+
+/// The unprocessed content of a section and the delimiters active where it was opened. Section
+/// lambdas receive [`SectionSource::raw`] and their output is rendered using these delimiters.
+#[derive(Clone, Debug, Default)]
+pub struct SectionSource {
+    /// The whole template, shared by every section in it rather than copied into each one
+    template: Arc<str>,
+    /// Byte range of the section content in `template`
+    content: Range<usize>,
+    pub left_delimiter: String,
+    pub right_delimiter: String,
+}
+
+impl SectionSource {
+    pub fn raw(&self) -> &str {
+        self.template.get(self.content.clone()).unwrap_or_default()
+    }
+}
+
+/// An open section tag waiting on its close tag
+#[derive(Debug)]
+pub struct OpenTag {
+    pub token: Token,
+    /// Index of the open tag in the token list
+    pub index: usize,
+    /// Byte offset in the template where the section content starts
+    pub content_start: usize,
 }
 
 pub fn tokenize(template: &str, ctx: &mut TokenCtx) -> Result<Vec<Token>, RibboncurlsError> {
     let mut tokens = Vec::new();
+    let shared_template: Arc<str> = Arc::from(template);
+    let mut cursor = LocationCursor::new(template, ctx.partial.clone());
     let mut i = 0;
 
     while i < template.len() {
         let current_str = &template[i..];
-        let single_char_left_delimiter = &ctx.left_delimiter[0..1];
         let left_delimiter_escape =
-            format!("{}{}", &ctx.left_delimiter, single_char_left_delimiter);
+            format!("{}{}", &ctx.left_delimiter, first_char(&ctx.left_delimiter));
 
         if current_str.starts_with(&left_delimiter_escape) {
-            let single_char_right_delimiter = &ctx.right_delimiter[0..1];
-            let right_delimiter_escape =
-                format!("{}{}", &ctx.right_delimiter, single_char_right_delimiter);
+            let right_delimiter_escape = format!(
+                "{}{}",
+                &ctx.right_delimiter,
+                first_char(&ctx.right_delimiter)
+            );
 
             if let Some(end) = current_str.find(&right_delimiter_escape) {
                 let end = end + i; // index in `template`
                 let content = &template[i + left_delimiter_escape.len()..end].trim();
+                let location = cursor.location(i, end + right_delimiter_escape.len() - i);
+
+                let token = Token::Variable((*content).to_string(), location);
 
-                tokens.push(Token::Variable((*content).to_string()));
+                tokens.push(token);
 
                 i = end + right_delimiter_escape.len();
             } else {
-                return Err(RibboncurlsError::MissingEndTag);
+                return Err(RibboncurlsError::MissingEndTag {
+                    delimiter: right_delimiter_escape,
+                    location: cursor.location(i, left_delimiter_escape.len()),
+                });
             }
         } else if current_str.starts_with(&ctx.left_delimiter) {
             // If there is a following end-delimiter
@@ -49,71 +97,264 @@ pub fn tokenize(template: &str, ctx: &mut TokenCtx) -> Result<Vec<Token>, Ribbon
                 if start_index < template.len() && end < template.len() {
                     let content = &template[start_index..end];
 
-                    if let Ok(token) = parse_tag(content, ctx) {
-                        if let Token::OpenSection(_) | Token::OpenInvertedSection(_) = token {
-                            ctx.section_stack.push(token.clone());
-                        }
-                        if let Token::CloseSection(ref close_section_name) = token {
-                            if let Some(Token::OpenSection(open_section_name)) =
-                                ctx.section_stack.last()
-                            {
-                                if close_section_name == open_section_name {
-                                    ctx.section_stack.pop();
-                                } else {
-                                    return Err(RibboncurlsError::MissingEndTag);
-                                }
-                            }
-                        }
-
-                        tokens.push(token);
-                    }
+                    let tag_end = end + right_delimiter_len;
+                    let token = parse_tag(content, cursor.location(i, tag_end - i), ctx)?;
+                    check_tag_name(&token, content)?;
+
+                    push_tag(token, tag_end, &shared_template, &mut tokens, ctx)?;
                 }
 
                 i = end + right_delimiter_len;
             } else {
-                return Err(RibboncurlsError::MissingEndTag);
+                return Err(RibboncurlsError::MissingEndTag {
+                    delimiter: ctx.right_delimiter.clone(),
+                    location: cursor.location(i, ctx.left_delimiter.len()),
+                });
             }
         } else {
             // Find the start of the next tag or end of the template
             if let Some(next_tag_start) = current_str.find(&ctx.left_delimiter) {
                 let text = &template[i..i + next_tag_start];
                 if !text.is_empty() {
-                    tokens.push(Token::Text(text.to_string()));
+                    tokens.push(Token::Text(
+                        text.to_string(),
+                        cursor.location(i, text.len()),
+                    ));
                 }
                 i += next_tag_start;
             // Otherwise add the remaining text
             } else {
                 let text = &template[i..];
-                tokens.push(Token::Text(text.to_string()));
+                tokens.push(Token::Text(
+                    text.to_string(),
+                    cursor.location(i, text.len()),
+                ));
                 break;
             }
         }
     }
 
+    // A section still open at the end of the template was never closed
+    if let Some(OpenTag {
+        token:
+            Token::OpenSection(name, _, location)
+            | Token::OpenInvertedSection(name, location)
+            | Token::OpenParent(name, location)
+            | Token::OpenBlock(name, location),
+        ..
+    }) = ctx.section_stack.pop()
+    {
+        return Err(RibboncurlsError::UnclosedSection { name, location });
+    }
+
     Ok(tokens)
 }
 
-fn parse_tag(content: &str, ctx: &mut TokenCtx) -> Result<Token, RibboncurlsError> {
+/// Pushes the token for a tag ending at `tag_end`, keeping track of the sections it opens and
+/// closes
+fn push_tag(
+    mut token: Token,
+    tag_end: usize,
+    template: &Arc<str>,
+    tokens: &mut Vec<Token>,
+    ctx: &mut TokenCtx,
+) -> Result<(), RibboncurlsError> {
+    if let Token::OpenSection(name, _, location)
+    | Token::OpenInvertedSection(name, location)
+    | Token::OpenParent(name, location)
+    | Token::OpenBlock(name, location) = &token
+    {
+        // Deeper sections would overflow the stack when the tree is built and rendered
+        if ctx.section_stack.len() >= ctx.max_section_depth {
+            return Err(RibboncurlsError::SectionDepth {
+                name: name.clone(),
+                limit: ctx.max_section_depth,
+                location: location.clone(),
+            });
+        }
+
+        ctx.section_stack.push(OpenTag {
+            token: token.clone(),
+            index: tokens.len(),
+            content_start: tag_end,
+        });
+    }
+    if let Token::CloseSection(close_section_name, location) = token {
+        token = close_section(template, close_section_name, location, tokens, ctx)?;
+    }
+
+    tokens.push(token);
+
+    Ok(())
+}
+
+/// Pops the matching open tag off the section stack and stores the raw section content on it.
+/// Returns the close tag token, which is a [`Token::CloseParent`] when it closes a parent, or an
+/// error if the close tag doesn't match the innermost open tag.
+fn close_section(
+    template: &Arc<str>,
+    close_section_name: String,
+    location: Location,
+    tokens: &mut [Token],
+    ctx: &mut TokenCtx,
+) -> Result<Token, RibboncurlsError> {
+    let Some(open_tag) = ctx.section_stack.last() else {
+        return Err(RibboncurlsError::UnexpectedCloseTag {
+            name: close_section_name,
+            location,
+        });
+    };
+
+    match &open_tag.token {
+        Token::OpenParent(open_section_name, _) if *open_section_name == close_section_name => {
+            ctx.section_stack.pop();
+
+            return Ok(Token::CloseParent(location));
+        }
+        Token::OpenSection(open_section_name, ..)
+        | Token::OpenInvertedSection(open_section_name, _)
+        | Token::OpenBlock(open_section_name, _)
+            if *open_section_name == close_section_name =>
+        {
+            if let Some(Token::OpenSection(_, source, _)) = tokens.get_mut(open_tag.index) {
+                source.template = Arc::clone(template);
+                source.content = open_tag.content_start..location.offset;
+            }
+            ctx.section_stack.pop();
+        }
+        Token::OpenSection(open_section_name, ..)
+        | Token::OpenInvertedSection(open_section_name, _)
+        | Token::OpenParent(open_section_name, _)
+        | Token::OpenBlock(open_section_name, _) => {
+            return Err(RibboncurlsError::MismatchedSection {
+                expected: open_section_name.clone(),
+                found: close_section_name,
+                location,
+            })
+        }
+        _ => {}
+    }
+
+    Ok(Token::CloseSection(close_section_name, location))
+}
+
+/// The first character of `text`, or nothing if `text` is empty
+fn first_char(text: &str) -> &str {
+    let len = text.chars().next().map_or(0, char::len_utf8);
+
+    &text[..len]
+}
+
+/// Parses the `content` of the tag at `location`
+fn parse_tag(
+    content: &str,
+    location: Location,
+    ctx: &mut TokenCtx,
+) -> Result<Token, RibboncurlsError> {
     match content.chars().next() {
-        Some('&') => Ok(Token::Variable(content[1..].trim().to_string())),
-        Some('#') => Ok(Token::OpenSection(content[1..].trim().to_string())),
-        Some('/') => Ok(Token::CloseSection(content[1..].trim().to_string())),
-        Some('^') => Ok(Token::OpenInvertedSection(content[1..].trim().to_string())),
-        Some('>') => Ok(Token::Partial(content[1..].trim().to_string())),
-        Some('!') => Ok(Token::Comment),
+        Some('&') => Ok(Token::Variable(content[1..].trim().to_string(), location)),
+        Some('#') => Ok(Token::OpenSection(
+            content[1..].trim().to_string(),
+            SectionSource {
+                left_delimiter: ctx.left_delimiter.clone(),
+                right_delimiter: ctx.right_delimiter.clone(),
+                ..SectionSource::default()
+            },
+            location,
+        )),
+        Some('/') => Ok(Token::CloseSection(
+            content[1..].trim().to_string(),
+            location,
+        )),
+        Some('^') => Ok(Token::OpenInvertedSection(
+            content[1..].trim().to_string(),
+            location,
+        )),
+        Some('<') => Ok(Token::OpenParent(content[1..].trim().to_string(), location)),
+        Some('$') => Ok(Token::OpenBlock(content[1..].trim().to_string(), location)),
+        Some('>') => {
+            let name = content[1..].trim();
+
+            // `{{>*name}}` takes the partial name from the data at `name`
+            Ok(name.strip_prefix('*').map_or_else(
+                || Token::Partial(name.to_string(), location.clone()),
+                |dynamic_name| {
+                    Token::DynamicPartial(dynamic_name.trim().to_string(), location.clone())
+                },
+            ))
+        }
+        Some('!') => Ok(Token::Comment(content[1..].to_string(), location)),
         Some('=') => {
-            let delimiters: Vec<&str> = content[1..content.len() - 1].trim().split(' ').collect();
+            let (left_delimiter, right_delimiter) = parse_delimiters(content, &location)?;
+            ctx.left_delimiter = left_delimiter;
+            ctx.right_delimiter = right_delimiter;
 
-            match (delimiters.first(), delimiters.last()) {
-                (Some(left_delimiter), Some(right_delimiter)) => {
-                    ctx.left_delimiter = (*left_delimiter).to_string();
-                    ctx.right_delimiter = (*right_delimiter).to_string();
+            Ok(Token::Delimiter(
+                ctx.left_delimiter.clone(),
+                ctx.right_delimiter.clone(),
+                location,
+            ))
+        }
+        _ => Ok(Token::EscapedVariable(content.trim().to_string(), location)),
+    }
+}
 
-                    Ok(Token::Delimiter)
+/// Returns an error for a section, parent, block or partial tag with `content` that has no name,
+/// such as `{{#}}`, which couldn't be closed or loaded
+fn check_tag_name(token: &Token, content: &str) -> Result<(), RibboncurlsError> {
+    match token {
+        Token::OpenSection(name, _, location)
+        | Token::CloseSection(name, location)
+        | Token::OpenInvertedSection(name, location)
+        | Token::OpenParent(name, location)
+        | Token::OpenBlock(name, location)
+        | Token::Partial(name, location)
+        | Token::DynamicPartial(name, location)
+            if name.is_empty() =>
+        {
+            Err(RibboncurlsError::BadTag {
+                tag: content.to_string(),
+                location: location.clone(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Parses the `=left right=` content of a set delimiter tag. The delimiters are separated by any
+/// whitespace and can't be empty or contain whitespace or `=`.
+fn parse_delimiters(
+    content: &str,
+    location: &Location,
+) -> Result<(String, String), RibboncurlsError> {
+    let bad_delimiter = |message: String| RibboncurlsError::BadDelimiter {
+        tag: content.to_string(),
+        message,
+        location: location.clone(),
+    };
+    let delimiters = content.strip_prefix('=').unwrap_or(content);
+    let (delimiters, has_end) = delimiters
+        .strip_suffix('=')
+        .map_or((delimiters, false), |delimiters| (delimiters, true));
+
+    match delimiters.split_whitespace().collect::<Vec<_>>()[..] {
+        [] | [_] => Err(RibboncurlsError::MissingDelimiter {
+            tag: content.to_string(),
+            location: location.clone(),
+        }),
+        _ if !has_end => Err(bad_delimiter("expected \"=\" at the end".to_string())),
+        [left_delimiter, right_delimiter] => {
+            for delimiter in [left_delimiter, right_delimiter] {
+                if let Some(message) = delimiter_problem(delimiter) {
+                    return Err(bad_delimiter(message));
                 }
-                _ => Err(RibboncurlsError::MissingDelimiter),
             }
+
+            Ok((left_delimiter.to_string(), right_delimiter.to_string()))
         }
-        _ => Ok(Token::EscapedVariable(content.trim().to_string())),
+        ref delimiters => Err(bad_delimiter(format!(
+            "expected 2 delimiters but found {}",
+            delimiters.len()
+        ))),
     }
 }
@@ -1,5 +1,3 @@
-use std::cmp::Ordering;
-
 use regex::Regex;
 
 pub fn escape_html(input: &str) -> String {
@@ -20,6 +18,33 @@ pub fn escape_html(input: &str) -> String {
     output
 }
 
+/// Splits `text` into lines, each line after the first starting with its own `\n` or `\r\n`, so
+/// that templates with mixed line endings keep each of them
+pub fn split_lines(text: &str) -> Vec<&str> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+
+    for (index, _) in text.match_indices('\n') {
+        let end = if text[..index].ends_with('\r') {
+            index - 1
+        } else {
+            index
+        };
+        lines.push(&text[start..end]);
+        start = end;
+    }
+    lines.push(&text[start..]);
+
+    lines
+}
+
+/// Splits a leading `\n` or `\r\n` off `text`
+pub fn strip_newline(text: &str) -> Option<(&str, &str)> {
+    ["\r\n", "\n"]
+        .into_iter()
+        .find_map(|newline| Some((newline, text.strip_prefix(newline)?)))
+}
+
 pub fn get_prev_item<T>(data: &[T], index: usize) -> Option<&T> {
     if index > 0 {
         data.get(index - 1)
@@ -29,37 +54,11 @@ pub fn get_prev_item<T>(data: &[T], index: usize) -> Option<&T> {
 }
 
 pub fn get_next_item<T>(data: &[T], index: usize) -> Option<&T> {
-    if index < data.len() - 1 {
-        data.get(index + 1)
-    } else {
-        None
-    }
-}
-
-#[derive(Clone, Copy, Debug)]
-pub enum Newline {
-    Crlf,
-    Lf,
-}
-
-impl Newline {
-    pub const fn as_str(self) -> &'static str {
-        match self {
-            Self::Lf => "\n",
-            Self::Crlf => "\r\n",
-        }
-    }
-
-    pub(crate) fn to_regex(self) -> Regex {
-        match self {
-            #[allow(clippy::trivial_regex)]
-            Self::Lf => Regex::new(r"\n").expect("Unable to get regex for '\\n'"),
-            #[allow(clippy::trivial_regex)]
-            Self::Crlf => Regex::new(r"\r\n").expect("Unable to get regex for '\\r\\n'"),
-        }
-    }
+    data.get(index.checked_add(1)?)
 }
 
+/// Regexes for text next to a newline, which is either `\n` or `\r\n` on each line
+#[derive(Clone, Copy)]
 pub enum NewlineRegex {
     StartsWithNewline,
     EndsWithNewlineFollowedByWhitespace,
@@ -67,49 +66,13 @@ pub enum NewlineRegex {
     StartsWithNewlineFollowedByWhitespaceUntilEnd,
 }
 
-pub fn get_newline_variant(text: &str) -> Newline {
-    let re_crlf = Newline::Crlf.to_regex();
-    let re_lf = Newline::Lf.to_regex();
+pub fn get_regex_for_newline(newline_regex: NewlineRegex) -> Regex {
+    let pattern = match newline_regex {
+        NewlineRegex::StartsWithNewline => r"^\r?\n",
+        NewlineRegex::EndsWithNewlineFollowedByWhitespace => r"\r?\n[ \t]*\z",
+        NewlineRegex::StartsWithNewlineFollowedByWhitespace => r"^\r?\n[ \t]*",
+        NewlineRegex::StartsWithNewlineFollowedByWhitespaceUntilEnd => r"^\r?\n[ \t]*\z",
+    };
 
-    let count_crlf = re_crlf.find_iter(text).count();
-    let count_lf = re_lf.find_iter(text).count();
-
-    match count_crlf.cmp(&count_lf) {
-        Ordering::Equal | Ordering::Greater => Newline::Crlf,
-        Ordering::Less => Newline::Lf,
-    }
-}
-
-pub fn get_regex_for_newline(newline_regex: NewlineRegex, newline: Newline) -> Regex {
-    match (newline_regex, newline) {
-        (NewlineRegex::StartsWithNewline, Newline::Lf) =>
-        {
-            #[allow(clippy::trivial_regex)]
-            Regex::new(r"^\n").expect("Unable to get regex")
-        }
-        (NewlineRegex::EndsWithNewlineFollowedByWhitespace, Newline::Lf) => {
-            Regex::new(r"\n[ \t]*\z").expect("Unable to get regex")
-        }
-        (NewlineRegex::StartsWithNewlineFollowedByWhitespace, Newline::Lf) => {
-            Regex::new(r"^\n[ \t]*").expect("Unable to get regex")
-        }
-        (NewlineRegex::StartsWithNewlineFollowedByWhitespaceUntilEnd, Newline::Lf) => {
-            Regex::new(r"^\n[ \t]*\z").expect("Unable to get regex")
-        }
-
-        (NewlineRegex::StartsWithNewline, Newline::Crlf) =>
-        {
-            #[allow(clippy::trivial_regex)]
-            Regex::new(r"^\r\n").expect("Unable to get regex")
-        }
-        (NewlineRegex::EndsWithNewlineFollowedByWhitespace, Newline::Crlf) => {
-            Regex::new(r"\r\n[ \t]*\z").expect("Unable to get regex")
-        }
-        (NewlineRegex::StartsWithNewlineFollowedByWhitespace, Newline::Crlf) => {
-            Regex::new(r"^\r\n[ \t]*").expect("Unable to get regex")
-        }
-        (NewlineRegex::StartsWithNewlineFollowedByWhitespaceUntilEnd, Newline::Crlf) => {
-            Regex::new(r"^\r\n[ \t]*\z").expect("Unable to get regex")
-        }
-    }
+    Regex::new(pattern).expect("Unable to get regex")
 }
@@ -0,0 +1,165 @@
+#![allow(clippy::unwrap_used, clippy::needless_raw_string_hashes)]
+
+use ribboncurls::ast::{self, Node, Tag};
+
+/// Writes the nodes back out as template source
+fn unparse(nodes: &[Node], out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text { text, .. } => out.push_str(text),
+            Node::Variable { tag, .. }
+            | Node::Partial { tag, .. }
+            | Node::Comment { tag, .. }
+            | Node::SetDelimiters { tag, .. } => out.push_str(&tag.text),
+            Node::Section {
+                children,
+                open,
+                close,
+                ..
+            }
+            | Node::Parent {
+                children,
+                open,
+                close,
+                ..
+            }
+            | Node::Block {
+                children,
+                open,
+                close,
+                ..
+            } => {
+                out.push_str(&open.text);
+                unparse(children, out);
+                out.push_str(&close.text);
+            }
+            _ => panic!("unexpected node: {node:?}"),
+        }
+    }
+}
+
+fn tag_at<'a>(template: &str, tag: &'a Tag) -> &'a str {
+    assert_eq!(
+        &template[tag.location.offset..tag.location.offset + tag.location.len],
+        tag.text
+    );
+
+    &tag.text
+}
+
+#[test]
+fn ast_round_trips_to_the_template() {
+    let template = r#"Hi {{ name }}, {{{raw}}} {{&amp}}
+{{#colors}}
+  {{! a comment }}
+  {{^dark}}{{> swatch}}{{/dark}}
+{{/colors}}
+{{<layout}}{{$body}}x{{/body}}{{/layout}}
+{{=<% %>=}}
+<%>*dynamic%> <%&amp%>
+"#;
+    let ast = ast::parse(template).unwrap();
+    let mut source = String::new();
+    unparse(&ast.nodes, &mut source);
+
+    assert_eq!(source, template);
+}
+
+#[test]
+fn ast_nodes_have_names_spans_and_flags() {
+    let template = "{{=<% %>=}}\n<%#colors%>\n  <%! note %>\n  <%&amp%> <%name%>\n<%/colors%>";
+    let ast = ast::parse(template).unwrap();
+
+    let [Node::SetDelimiters { left, right, tag }, Node::Text { .. }, Node::Section {
+        name,
+        inverted: false,
+        children,
+        open,
+        close,
+    }] = ast.nodes.as_slice()
+    else {
+        panic!("unexpected nodes: {:?}", ast.nodes);
+    };
+    assert_eq!((left.as_str(), right.as_str()), ("<%", "%>"));
+    assert_eq!(tag_at(template, tag), "{{=<% %>=}}");
+    assert!(tag.standalone);
+    assert_eq!(name, "colors");
+    assert_eq!(tag_at(template, open), "<%#colors%>");
+    assert_eq!((open.location.line, open.location.column), (2, 1));
+    assert_eq!(tag_at(template, close), "<%/colors%>");
+    assert!(open.standalone && close.standalone);
+
+    let tags: Vec<_> = children
+        .iter()
+        .filter_map(|node| match node {
+            Node::Comment { text, tag } => Some((text.as_str(), tag.standalone)),
+            Node::Variable { name, escaped, .. } => Some((name.as_str(), *escaped)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(tags, [(" note ", true), ("amp", false), ("name", true)]);
+}
+
+#[test]
+fn ast_parents_blocks_and_partials() {
+    let template = "{{<layout}}\n{{$title}}Hi{{/title}}\n{{/layout}}\n{{>*which}} {{> footer}}";
+    let ast = ast::parse(template).unwrap();
+
+    let Node::Parent {
+        name,
+        children,
+        open,
+        close,
+    } = &ast.nodes[0]
+    else {
+        panic!("unexpected node: {:?}", ast.nodes[0]);
+    };
+    assert_eq!(name, "layout");
+    assert!(open.standalone && close.standalone);
+    assert!(children.iter().any(|node| matches!(
+        node,
+        Node::Block { name, open, .. } if name == "title" && !open.standalone
+    )));
+
+    let partials: Vec<_> = ast
+        .nodes
+        .iter()
+        .filter_map(|node| match node {
+            Node::Partial { name, dynamic, tag } => Some((name.as_str(), *dynamic, tag.standalone)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(partials, [("which", true, false), ("footer", false, false)]);
+}
+
+#[test]
+fn ast_parse_with_delimiters() {
+    let template = "{{ .Values }}\n<%#items%>\n  <% name %>\n<%/items%>\n";
+    let ast = ast::parse_with_delimiters(template, &"<% %>".parse().unwrap()).unwrap();
+    let mut source = String::new();
+    unparse(&ast.nodes, &mut source);
+
+    assert_eq!(source, template);
+    let Node::Section { open, children, .. } = &ast.nodes[1] else {
+        panic!("expected a section: {:?}", ast.nodes);
+    };
+    assert_eq!(tag_at(template, open), "<%#items%>");
+    assert!(open.standalone);
+    assert!(matches!(&children[1], Node::Variable { name, .. } if name == "name"));
+
+    // The default delimiters are only text
+    assert!(matches!(&ast.nodes[0], Node::Text { text, .. } if text == "{{ .Values }}\n"));
+}
+
+#[test]
+fn ast_parse_errors() {
+    assert!(matches!(
+        ast::parse("{{#a}}{{/b}}"),
+        Err(ribboncurls::RibboncurlsError::MismatchedSection { .. })
+    ));
+    assert!(matches!(
+        ast::parse("{{#a}}"),
+        Err(ribboncurls::RibboncurlsError::UnclosedSection { .. })
+    ));
+    assert_eq!(ast::parse("").unwrap().nodes, []);
+}
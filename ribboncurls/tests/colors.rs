@@ -0,0 +1,113 @@
+#![cfg(feature = "colors")]
+#![allow(clippy::unwrap_used)]
+
+use ribboncurls::{color::Color, RibboncurlsError};
+
+const SCHEME: &str = r##"
+base00: "181818"
+base08: "#ab4642"
+base0D: "0x7cafc2"
+short: "#f80"
+translucent: "7cafc280"
+"##;
+
+fn render(template: &str) -> String {
+    ribboncurls::render(template, SCHEME, None).unwrap()
+}
+
+#[test]
+fn color_parse() {
+    assert_eq!(Color::parse("7cafc2"), Some(Color::rgb(124, 175, 194)));
+    assert_eq!(Color::parse("#7CAFC2"), Some(Color::rgb(124, 175, 194)));
+    assert_eq!(Color::parse("0x7cafc2"), Some(Color::rgb(124, 175, 194)));
+    assert_eq!(Color::parse("#f80"), Some(Color::rgb(255, 136, 0)));
+    assert_eq!(
+        Color::parse("7cafc280"),
+        Some(Color::rgba(124, 175, 194, 128))
+    );
+
+    for text in ["", "#", "7cafc", "7cafc2f", "#ggffff", "#+1ffff", "🎨🎨"] {
+        assert_eq!(Color::parse(text), None, "{text}");
+    }
+}
+
+#[test]
+fn color_formats() {
+    assert_eq!(
+        render("{{ base08 | hex }} {{ base0D | hex }} {{ short | hex }} {{ translucent | hex }}"),
+        "ab4642 7cafc2 ff8800 7cafc280"
+    );
+    assert_eq!(
+        render(
+            "{{ base0D | rgb }}|{{ base0D | rgb:\"r\" }} {{ base0D | rgb:g }} {{ base0D | rgb:b }}"
+        ),
+        "124, 175, 194|124 175 194"
+    );
+    assert_eq!(
+        render("{{ base0D | rgba }}|{{ translucent | rgba }}"),
+        "124, 175, 194, 1|124, 175, 194, 0.502"
+    );
+    assert_eq!(
+        render("{{ base0D | dec }}|{{ base00 | dec:r }} {{ base08 | dec:\"g\" }} {{ translucent | dec:a }}"),
+        "0.4863, 0.6863, 0.7608|0.0941 0.2745 0.502"
+    );
+    assert_eq!(
+        render("{{ base0D | hsl }}|{{ base08 | hsl }}|{{ base00 | hsl }}|{{ base0D | hsl:h }} {{ base0D | hsl:s }} {{ base0D | hsl:l }}"),
+        "196, 36%, 62%|2, 44%, 46%|0, 0%, 9%|196 36 62"
+    );
+}
+
+#[test]
+fn color_changes() {
+    assert_eq!(
+        render("{{ base0D | lighten:10% }} {{ base0D | darken:0.1 }} {{ base08 | lighten:0.1 }} {{ base08 | darken:20% }}"),
+        "9fc4d2 599ab2 c1635f 612826"
+    );
+    assert_eq!(
+        render("{{ base00 | lighten:2 }} {{ base00 | darken:100% }} {{ translucent | lighten:0 }}"),
+        "ffffff 000000 7cafc280"
+    );
+    assert_eq!(
+        render("{{ base08 | mix:\"181818\",0.25 }} {{ base00 | mix:\"#ffffff\" }} {{ base00 | mix:ffffff,0 }} {{ base0D | mix:\"181818\",25% }}"),
+        "863b38 8c8c8c 181818 638998"
+    );
+    assert_eq!(
+        render(
+            "{{ base0D | alpha:50% }} {{ translucent | alpha:1 }} {{ base0D | alpha:0 | rgba }}"
+        ),
+        "7cafc280 7cafc2 124, 175, 194, 0"
+    );
+    assert_eq!(
+        render("#{{ base0D | darken:10% | upper }} rgb({{ base0D | darken:10% | rgb }})"),
+        "#599AB2 rgb(89, 154, 178)"
+    );
+}
+
+#[test]
+fn color_filter_errors() {
+    let filter_error = |template: &str| match ribboncurls::render(template, SCHEME, None) {
+        Err(RibboncurlsError::Filter { name, message, .. }) => format!("{name}: {message}"),
+        result => panic!("expected a filter error: {result:?}"),
+    };
+
+    assert_eq!(
+        filter_error("{{ base00 | upper | rgb:x }}"),
+        "rgb: \"x\" isn't one of r, g, b"
+    );
+    assert_eq!(
+        filter_error("{{ base00 | lighten:lots }}"),
+        "lighten: \"lots\" is not an amount"
+    );
+    assert_eq!(
+        filter_error("{{ base00 | mix:\"red\" }}"),
+        "mix: \"red\" is not a hex color"
+    );
+    assert_eq!(
+        filter_error("{{ base00 | rgb | hex }}"),
+        "hex: \"24, 24, 24\" is not a hex color"
+    );
+    assert_eq!(
+        filter_error("{{ base00 | hsl:h,s }}"),
+        "hsl: expected at most 1 argument but found 2"
+    );
+}
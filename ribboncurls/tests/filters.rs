@@ -0,0 +1,128 @@
+#![cfg(feature = "filters")]
+#![allow(clippy::unwrap_used, clippy::needless_raw_string_hashes)]
+
+use ribboncurls::{Filters, RibboncurlsError, StrictMode, Template};
+
+#[test]
+fn standard_filters() {
+    let template = r#"{{ name | upper }} {{ name|lower }} {{ slug | replace:"-"," " | capitalize }} [{{ padded | trim }}]"#;
+    let data = r#"
+        name: Tinted Theming
+        slug: base16-default-dark
+        padded: "  x  "
+    "#;
+    let result = ribboncurls::render(template, data, None).unwrap();
+
+    assert_eq!(
+        result,
+        "TINTED THEMING tinted theming Base16 default dark [x]"
+    );
+}
+
+#[test]
+fn default_filter() {
+    let template = Template::compile(
+        r#"{{ author | default:"unknown" }} {{ empty | default:none }} {{{ raw | default:"<b>" }}}"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        template.render("author: Tinted\nempty: ''").unwrap(),
+        "Tinted none <b>"
+    );
+
+    // A missing value with a default isn't an error in strict mode
+    let template = template.with_strict_mode(StrictMode::new());
+    assert_eq!(template.render("{}").unwrap(), "unknown none <b>");
+    assert!(matches!(
+        Template::compile("{{ author | upper }}")
+            .unwrap()
+            .with_strict_mode(StrictMode::new())
+            .render("{}"),
+        Err(RibboncurlsError::MissingData { path, .. }) if path == "author"
+    ));
+}
+
+#[test]
+fn filtered_values_are_escaped() {
+    let template = r#"{{ name | replace:"x","<&>" }} {{{ name | replace:"x","<&>" }}}"#;
+    let result = ribboncurls::render(template, "name: axb", None).unwrap();
+
+    assert_eq!(result, "a&lt;&amp;&gt;b a<&>b");
+}
+
+#[test]
+fn quoted_arguments() {
+    let template = r#"{{ name | replace:"|","\"" | replace:",",":" }}"#;
+    let result = ribboncurls::render(template, "name: 'a|b,c'", None).unwrap();
+
+    assert_eq!(result, "a&quot;b:c");
+}
+
+#[test]
+fn custom_filters() {
+    let filters = || {
+        let mut filters = Filters::new();
+        filters
+            .filter("repeat", |value, args| {
+                let count = args
+                    .first()
+                    .and_then(|count| count.parse().ok())
+                    .ok_or("expected a count")?;
+                Ok(value.repeat(count))
+            })
+            .filter("upper", |value, _| Ok(format!("{value}!")));
+        filters
+    };
+
+    let template = Template::compile("{{ name | repeat:2 }} {{ name | upper | lower }}")
+        .unwrap()
+        .with_filters(filters());
+    assert_eq!(template.render("name: aB").unwrap(), "aBaB ab!");
+
+    let template = Template::compile("{{ name | repeat }}")
+        .unwrap()
+        .with_filters(filters());
+    assert!(matches!(
+        template.render("name: ab"),
+        Err(RibboncurlsError::Filter { name, message, .. })
+            if name == "repeat" && message == "expected a count"
+    ));
+}
+
+#[test]
+fn filter_errors() {
+    let bad_filter = |template: &str| match Template::compile(template) {
+        Err(RibboncurlsError::BadFilter { location, .. }) => location.column,
+        result => panic!("expected a bad filter error: {result:?}"),
+    };
+
+    assert_eq!(bad_filter(r#"ab {{ name | default:"x }}"#), 4);
+    assert_eq!(bad_filter("{{ name | }}"), 1);
+    assert_eq!(bad_filter("{{ | upper }}"), 1);
+    assert_eq!(bad_filter("{{ name | default: }}"), 1);
+    assert_eq!(bad_filter("{{& name | bad name }}"), 1);
+    assert_eq!(bad_filter("{{{ name | replace:a\"b\",c }}}"), 1);
+    assert!(matches!(
+        ribboncurls::ast::parse("{{#a}}{{ name | }}{{/a}}"),
+        Err(RibboncurlsError::BadFilter { .. })
+    ));
+
+    assert!(matches!(
+        ribboncurls::render("{{ name | shout }}", "name: a", None),
+        Err(RibboncurlsError::UnknownFilter { name, .. }) if name == "shout"
+    ));
+    assert!(matches!(
+        ribboncurls::render(r#"{{ name | replace:"a" }}"#, "name: a", None),
+        Err(RibboncurlsError::Filter { name, .. }) if name == "replace"
+    ));
+}
+
+#[test]
+fn references_leave_out_filters() {
+    let template = Template::compile(r#"{{#scheme}}{{ name | default:"x" }}{{/scheme}}"#).unwrap();
+    let references = template.references();
+
+    assert_eq!(references[1].name, "name");
+    assert_eq!(references[1].path, ["scheme"]);
+}
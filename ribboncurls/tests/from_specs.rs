@@ -1 +1,3 @@
+#![allow(clippy::needless_raw_string_hashes)]
+
 include!(concat!(env!("OUT_DIR"), "/from_specs.rs"));
@@ -1,3 +1,5 @@
+#![allow(clippy::unwrap_used, clippy::needless_raw_string_hashes)]
+
 #[test]
 fn test_output_unicode() {
     let template = r#"Url is: {{url}}!"#;
@@ -258,3 +260,828 @@ fn nested_inverted_section() {
 
     assert_eq!(template, "Ribboncurls!");
 }
+
+#[test]
+fn compiled_template_renders_many_times() {
+    let template = ribboncurls::Template::compile("{{#schemes}}{{> name}}{{/schemes}}")
+        .unwrap()
+        .with_partials("name: '{{name}};'")
+        .unwrap();
+
+    assert_eq!(
+        template
+            .render("schemes: [{ name: a }, { name: b }]")
+            .unwrap(),
+        "a;b;"
+    );
+    assert_eq!(template.render("schemes: [{ name: c }]").unwrap(), "c;");
+}
+
+#[test]
+fn compiled_template_is_shared_between_threads() {
+    let template = ribboncurls::Template::compile("{{> greeting}}, {{name}}!")
+        .unwrap()
+        .with_partials("greeting: Hello")
+        .unwrap();
+
+    std::thread::scope(|scope| {
+        for name in ["one", "two", "three"] {
+            let template = &template;
+            scope.spawn(move || {
+                let result = template.render(&format!("name: {name}")).unwrap();
+
+                assert_eq!(result, format!("Hello, {name}!"));
+            });
+        }
+    });
+}
+
+#[test]
+fn render_with_serializable_data() {
+    #[derive(serde::Serialize)]
+    struct Color {
+        name: String,
+        hex: String,
+    }
+
+    #[derive(serde::Serialize)]
+    struct Scheme {
+        name: String,
+        dark: bool,
+        colors: Vec<Color>,
+    }
+
+    let scheme = Scheme {
+        name: "Tinted".to_string(),
+        dark: true,
+        colors: vec![
+            Color {
+                name: "base00".to_string(),
+                hex: "181818".to_string(),
+            },
+            Color {
+                name: "base01".to_string(),
+                hex: "282828".to_string(),
+            },
+        ],
+    };
+    let partials =
+        std::collections::HashMap::from([("color".to_string(), "{{name}}=#{{hex}};".to_string())]);
+    let template = "{{name}}{{#dark}} (dark){{/dark}}: {{#colors}}{{> color}}{{/colors}}";
+    let result = ribboncurls::render_with(template, &scheme, &partials).unwrap();
+
+    assert_eq!(result, "Tinted (dark): base00=#181818;base01=#282828;");
+}
+
+#[test]
+fn render_with_yaml_value() {
+    let data: serde_yaml::Value = serde_yaml::from_str("name: Tinted").unwrap();
+    let template = ribboncurls::Template::compile("Hello, {{name}}!").unwrap();
+
+    assert_eq!(template.render_with(&data).unwrap(), "Hello, Tinted!");
+}
+
+#[test]
+fn nested_partial_indentation_is_exact_and_cumulative() {
+    let template = ribboncurls::Template::compile("colors:\n\t {{> outer}}\nend\n")
+        .unwrap()
+        .with_partials(
+            r#"
+            outer: "outer:\n  \t{{> inner}}\n{{name}}: done\n"
+            inner: "{{name}}\n\tinner {{name}}!\n"
+            "#,
+        )
+        .unwrap();
+
+    assert_eq!(
+        template.render("name: base").unwrap(),
+        "colors:\n\t outer:\n\t   \tbase\n\t   \t\tinner base!\n\t base: done\nend\n"
+    );
+}
+
+#[test]
+fn partial_indentation_has_no_length_limit() {
+    let indent = " \t".repeat(200);
+    let template = ribboncurls::Template::compile(&format!("{indent}{{{{> lines}}}}\n"))
+        .unwrap()
+        .with_partials("lines: \"a\\nb\\n\"")
+        .unwrap();
+
+    assert_eq!(
+        template.render("{}").unwrap(),
+        format!("{indent}a\n{indent}b\n")
+    );
+}
+
+#[test]
+fn inline_partials_are_not_indented() {
+    let template = ribboncurls::Template::compile("a\n  x {{> lines}}\n")
+        .unwrap()
+        .with_partials("lines: \"{{name}}!\\nb\\n\"")
+        .unwrap();
+
+    assert_eq!(template.render("name: n").unwrap(), "a\n  x n!\nb\n\n");
+}
+
+#[test]
+fn mixed_line_endings_are_handled_per_line() {
+    let template = ribboncurls::Template::compile(
+        "{{#colors}}\r\n  {{> color}}\n{{/colors}}\n{{! comment }}\r\nend\n",
+    )
+    .unwrap()
+    .with_partials("color: \"{{name}}:\\r\\n\\t{{hex}}\\n\"")
+    .unwrap();
+    let data = "colors: [{name: red, hex: ab4642}, {name: blue, hex: 7cafc2}]";
+
+    assert_eq!(
+        template.render(data).unwrap(),
+        "  red:\r\n  \tab4642\n  blue:\r\n  \t7cafc2\nend\n"
+    );
+}
+
+#[test]
+fn line_endings_are_normalized() {
+    use ribboncurls::LineEndings;
+
+    // `c\r` and the `\n` after it are written separately but are still one line ending
+    let render = |line_endings| {
+        ribboncurls::Template::compile("{{#lines}}\r\n{{{.}}}\n{{/lines}}\r\n{{{cr}}}")
+            .unwrap()
+            .with_line_endings(line_endings)
+            .render(r#"{lines: ["a\r\nb", "c\r", d], cr: "\r"}"#)
+            .unwrap()
+    };
+
+    assert_eq!(render(LineEndings::Preserve), "a\r\nb\nc\r\nd\n\r");
+    assert_eq!(render(LineEndings::Lf), "a\nb\nc\nd\n\r");
+    assert_eq!(render(LineEndings::Crlf), "a\r\nb\r\nc\r\nd\r\n\r");
+}
+
+#[test]
+fn partial_depth_is_limited() {
+    use ribboncurls::{Limits, RibboncurlsError};
+
+    // A partial that includes itself fails instead of overflowing the stack
+    let template = ribboncurls::Template::compile("{{> loop}}")
+        .unwrap()
+        .with_partials("loop: \"{{#a}}\\n  {{> loop}}\\n{{/a}}{{<loop}}{{/loop}}\"")
+        .unwrap();
+    assert!(matches!(
+        template.render("a: true"),
+        Err(RibboncurlsError::PartialDepth { name, limit: 100, location })
+            if name == "loop" && location.partial.as_deref() == Some("loop")
+    ));
+
+    let template = ribboncurls::Template::compile("{{> outer}}")
+        .unwrap()
+        .with_partials("{outer: '[{{> inner}}]', inner: '{{name}}'}")
+        .unwrap();
+    assert_eq!(
+        template
+            .with_limits(Limits::new().partial_depth(2))
+            .render("name: x")
+            .unwrap(),
+        "[x]"
+    );
+
+    let template = ribboncurls::Template::compile("{{> outer}}")
+        .unwrap()
+        .with_partials("{outer: '[{{> inner}}]', inner: '{{name}}'}")
+        .unwrap()
+        .with_limits(Limits::new().partial_depth(1));
+    assert!(matches!(
+        template.render("name: x"),
+        Err(RibboncurlsError::PartialDepth { name, limit: 1, .. }) if name == "inner"
+    ));
+
+    // Nothing is written for a partial that is too deep, not even its indentation
+    let template = ribboncurls::Template::compile("  {{> a}}")
+        .unwrap()
+        .with_partials("a: x")
+        .unwrap()
+        .with_limits(Limits::new().partial_depth(0));
+    let mut output = Vec::new();
+    assert!(matches!(
+        template.render_to("{}", &mut output),
+        Err(RibboncurlsError::PartialDepth { limit: 0, .. })
+    ));
+    assert_eq!(output, b"");
+}
+
+#[test]
+fn section_depth_is_limited() {
+    use ribboncurls::{Limits, RibboncurlsError, Template};
+
+    // Deeply nested sections fail to compile instead of overflowing the stack
+    let nested = |depth: usize| format!("{}x{}", "{{#a}}".repeat(depth), "{{/a}}".repeat(depth));
+    assert!(Template::compile(&nested(100)).is_ok());
+    assert!(matches!(
+        Template::compile(&nested(101)),
+        Err(RibboncurlsError::SectionDepth { name, limit: 100, location })
+            if name == "a" && location.column == 601
+    ));
+
+    // Partials are parsed with the limits of the template that renders them
+    let template =
+        Template::compile_with_limits("{{#a}}{{> p}}{{/a}}", Limits::new().section_depth(1))
+            .unwrap()
+            .with_partials("p: '{{^b}}{{<c}}{{/c}}{{/b}}'")
+            .unwrap();
+    assert!(matches!(
+        template.render("a: true"),
+        Err(RibboncurlsError::SectionDepth { name, limit: 1, location })
+            if name == "c" && location.partial.as_deref() == Some("p")
+    ));
+    assert_eq!(
+        template
+            .with_limits(Limits::new().section_depth(2))
+            .render("a: true")
+            .unwrap(),
+        ""
+    );
+
+    // Sections in partials add to the sections around the partial tag, so a partial that
+    // includes itself from inside sections fails instead of overflowing the stack
+    let partial = format!("{}{{{{> p}}}}{}", "{{#a}}".repeat(80), "{{/a}}".repeat(80));
+    let template =
+        Template::compile("{{> p}}")
+            .unwrap()
+            .with_partial_map(std::collections::HashMap::from([(
+                "p".to_string(),
+                partial,
+            )]));
+    assert!(matches!(
+        template.render("a: true"),
+        Err(RibboncurlsError::SectionDepth { name, limit: 100, location })
+            if name == "a" && location.partial.as_deref() == Some("p") && location.column == 121
+    ));
+
+    // Blocks count too
+    let template = Template::compile("{{#a}}{{<p}}{{/p}}{{/a}}")
+        .unwrap()
+        .with_partials("p: '{{$b}}{{$c}}x{{/c}}{{/b}}'")
+        .unwrap();
+    assert_eq!(template.render("a: true").unwrap(), "x");
+    assert!(matches!(
+        template
+            .with_limits(Limits::new().section_depth(2))
+            .render("a: true"),
+        Err(RibboncurlsError::SectionDepth { name, limit: 2, .. }) if name == "c"
+    ));
+}
+
+#[test]
+fn output_size_and_iterations_are_limited() {
+    use ribboncurls::{Limits, LineEndings, RibboncurlsError};
+
+    let template = ribboncurls::Template::compile("{{#items}}{{.}}\n{{/items}}")
+        .unwrap()
+        .with_limits(Limits::new().output_bytes(6));
+    assert_eq!(template.render("items: [a, b, c]").unwrap(), "a\nb\nc\n");
+    assert!(matches!(
+        template.render("items: [a, b, c, d]"),
+        Err(RibboncurlsError::StringSize { limit: 6 })
+    ));
+
+    // Line endings are changed before the output is measured
+    let template = template.with_line_endings(LineEndings::Crlf);
+    assert!(matches!(
+        template.render("items: [a, b, c]"),
+        Err(RibboncurlsError::StringSize { limit: 6 })
+    ));
+
+    // Iterations are counted across all sections, nested ones included
+    let template = ribboncurls::Template::compile("{{#items}}{{#items}}{{.}}{{/items}}{{/items}}")
+        .unwrap()
+        .with_limits(Limits::new().iterations(6));
+    assert_eq!(template.render("items: [a, b]").unwrap(), "abab");
+    assert!(matches!(
+        template.render("items: [a, b, c]"),
+        Err(RibboncurlsError::Iterations { limit: 6, location }) if location.column == 11
+    ));
+}
+
+#[test]
+fn directory_loader_loads_nested_partials() {
+    let dir = std::env::temp_dir().join("ribboncurls-test-directory-loader");
+    std::fs::create_dir_all(dir.join("colors/dark")).unwrap();
+    std::fs::write(dir.join("header.mustache"), "# {{name}}\n").unwrap();
+    std::fs::write(dir.join("colors/dark/base.mustache"), "#{{base00}}").unwrap();
+
+    let template = ribboncurls::Template::compile(
+        "{{> header}}{{> colors/dark/base}}{{> missing}}{{> ../header}}",
+    )
+    .unwrap()
+    .with_partial_loader(ribboncurls::DirectoryLoader::new(&dir));
+    let result = template.render("{name: Tinted, base00: 181818}").unwrap();
+
+    assert_eq!(result, "# Tinted\n#181818");
+}
+
+#[test]
+fn partial_loader_is_called_once_per_partial() {
+    struct CountingLoader(std::sync::atomic::AtomicUsize);
+
+    impl ribboncurls::PartialLoader for CountingLoader {
+        fn load(&self, name: &str) -> Result<Option<String>, ribboncurls::RibboncurlsError> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+
+            Ok((name == "item").then(|| "<{{.}}>".to_string()))
+        }
+    }
+
+    let loader = std::sync::Arc::new(CountingLoader(std::sync::atomic::AtomicUsize::new(0)));
+    let template = ribboncurls::Template::compile("{{#items}}{{> item}}{{> missing}}{{/items}}")
+        .unwrap()
+        .with_partial_loader(std::sync::Arc::clone(&loader));
+
+    assert_eq!(template.render("items: [a, b]").unwrap(), "<a><b>");
+    assert_eq!(template.render("items: [c]").unwrap(), "<c>");
+    assert_eq!(loader.0.load(std::sync::atomic::Ordering::SeqCst), 2);
+}
+
+#[test]
+fn render_to_writes_into_io_and_fmt_writers() {
+    let template = ribboncurls::Template::compile("{{#items}}{{> item}}{{/items}}")
+        .unwrap()
+        .with_partials("item: '[{{.}}]'")
+        .unwrap();
+
+    let mut bytes: Vec<u8> = Vec::new();
+    template.render_to("items: [a, b, c]", &mut bytes).unwrap();
+    assert_eq!(bytes, b"[a][b][c]");
+
+    let mut text = String::from("items: ");
+    template.render_fmt("items: [d]", &mut text).unwrap();
+    assert_eq!(text, "items: [d]");
+}
+
+#[test]
+fn render_to_returns_write_errors() {
+    struct FailingWriter;
+
+    impl std::io::Write for FailingWriter {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "closed",
+            ))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let template = ribboncurls::Template::compile("{{name}}").unwrap();
+    let result = template.render_to("name: Tinted", &mut FailingWriter);
+
+    assert!(matches!(
+        result,
+        Err(ribboncurls::RibboncurlsError::Write(_))
+    ));
+}
+
+#[test]
+fn sequences_of_sequences_and_block_overrides_are_streamed() {
+    use ribboncurls::{Limits, RibboncurlsError};
+
+    // Output written before the size limit is hit shows that it wasn't rendered into a buffer
+    // first
+    let template = ribboncurls::Template::compile("{{#.}}{{#.}}{{.}}{{/.}}{{/.}}")
+        .unwrap()
+        .with_limits(Limits::new().output_bytes(4));
+    let mut output = Vec::new();
+    let result = template.render_to("[[a, b], [c, d], [e, f]]", &mut output);
+    assert!(matches!(
+        result,
+        Err(RibboncurlsError::StringSize { limit: 4 })
+    ));
+    assert_eq!(output, b"abcd");
+
+    let template = ribboncurls::Template::compile(
+        "{{<layout}}{{$body}}{{#items}}{{.}}{{/items}}{{/body}}{{/layout}}",
+    )
+    .unwrap()
+    .with_partials("layout: '{{$body}}{{/body}}'")
+    .unwrap()
+    .with_limits(Limits::new().output_bytes(2));
+    let mut output = Vec::new();
+    let result = template.render_to("items: [a, b, c]", &mut output);
+    assert!(matches!(
+        result,
+        Err(RibboncurlsError::StringSize { limit: 2 })
+    ));
+    assert_eq!(output, b"ab");
+}
+
+#[test]
+fn block_overrides_are_reindented_line_by_line() {
+    let template = ribboncurls::Template::compile(
+        "{{<layout}}\n{{$body}}\n{{a}}\r\n\r\n{{b}}\n\n{{c}}\n{{/body}}\n{{/layout}}",
+    )
+    .unwrap()
+    .with_partials("layout: \"<ul>\\n  {{$body}}\\n  x\\n  {{/body}}\\n</ul>\\n\"")
+    .unwrap();
+
+    assert_eq!(
+        template.render("{a: '1', b: '2', c: \"\\r3\"}").unwrap(),
+        "<ul>\n  1\r\n\r\n  2\n\n  \r3\n</ul>\n"
+    );
+}
+
+#[test]
+fn tags_without_names_are_bad_tags() {
+    for tag in [
+        "{{#}}", "{{^ }}", "{{/}}", "{{<}}", "{{$}}", "{{>}}", "{{> * }}",
+    ] {
+        let template = format!("x\n  {tag}");
+        let err = ribboncurls::Template::compile(&template).unwrap_err();
+
+        assert!(
+            matches!(&err, ribboncurls::RibboncurlsError::BadTag { location, .. } if (location.line, location.column) == (2, 3)),
+            "{tag}: {err:?}"
+        );
+    }
+
+    let err = ribboncurls::Template::compile("{{# }}").unwrap_err();
+    assert_eq!(err.to_string(), r##"bad tag "# " at line 1, column 1"##);
+
+    // Variables and comments don't need names
+    assert!(ribboncurls::Template::compile("{{}}{{&}}{{!}}").is_ok());
+}
+
+#[test]
+fn mismatched_section_error_has_location() {
+    let template = "Scheme:\n  {{#colors}}\n    ✓ {{name}}{{/color}}\n  {{/colors}}";
+    let err = ribboncurls::render(template, "colors: []", None).unwrap_err();
+
+    match &err {
+        ribboncurls::RibboncurlsError::MismatchedSection {
+            expected,
+            found,
+            location,
+        } => {
+            assert_eq!(expected, "colors");
+            assert_eq!(found, "color");
+            assert_eq!(location.offset, template.find("{{/color}}").unwrap());
+            assert_eq!((location.line, location.column), (3, 15));
+            assert_eq!(location.partial, None);
+        }
+        _ => panic!("unexpected error: {err:?}"),
+    }
+    assert_eq!(
+        err.to_string(),
+        r#"expected close tag for section "colors" but found "color" at line 3, column 15"#
+    );
+    assert_eq!(
+        err.location().unwrap().snippet(template),
+        "3 |     ✓ {{name}}{{/color}}\n  |               ^^^^^^^^^^"
+    );
+}
+
+#[test]
+fn missing_end_tag_error_in_partial_has_location() {
+    let err = ribboncurls::render("{{> header}}", "{}", Some("header: 'Hi {{name'")).unwrap_err();
+
+    match &err {
+        ribboncurls::RibboncurlsError::MissingEndTag {
+            delimiter,
+            location,
+        } => {
+            assert_eq!(delimiter, "}}");
+            assert_eq!((location.offset, location.line, location.column), (3, 1, 4));
+            assert_eq!(location.partial.as_deref(), Some("header"));
+        }
+        _ => panic!("unexpected error: {err:?}"),
+    }
+    assert_eq!(
+        err.to_string(),
+        r#"missing end tag "}}" at line 1, column 4 in partial "header""#
+    );
+}
+
+#[test]
+fn strict_mode_errors_on_missing_variable() {
+    let template = ribboncurls::Template::compile("{{#scheme}}\n{{scheme.author}}\n{{/scheme}}")
+        .unwrap()
+        .with_strict_mode(ribboncurls::StrictMode::new());
+    let err = template.render("scheme: { name: Tinted }").unwrap_err();
+
+    match &err {
+        ribboncurls::RibboncurlsError::MissingData { path, location } => {
+            assert_eq!(path, "scheme.author");
+            assert_eq!((location.line, location.column), (2, 1));
+        }
+        _ => panic!("unexpected error: {err:?}"),
+    }
+    assert_eq!(
+        err.to_string(),
+        r#"missing data "scheme.author" at line 2, column 1"#
+    );
+}
+
+#[test]
+fn strict_mode_allows_listed_and_inverted_sections() {
+    let template =
+        ribboncurls::Template::compile("{{name}}{{subtitle}}{{^variant}} (dark){{/variant}}")
+            .unwrap()
+            .with_strict_mode(ribboncurls::StrictMode::new().allow_missing("subtitle"));
+
+    assert_eq!(template.render("name: Tinted").unwrap(), "Tinted (dark)");
+    assert!(matches!(
+        template.render("subtitle: Theming"),
+        Err(ribboncurls::RibboncurlsError::MissingData { path, .. }) if path == "name"
+    ));
+}
+
+#[test]
+fn strict_mode_errors_on_missing_section_and_partial() {
+    let section = ribboncurls::Template::compile("{{#colors}}x{{/colors}}")
+        .unwrap()
+        .with_strict_mode(ribboncurls::StrictMode::new());
+    assert!(matches!(
+        section.render("{}"),
+        Err(ribboncurls::RibboncurlsError::MissingData { path, .. }) if path == "colors"
+    ));
+    // Sections with false or empty values are still present
+    assert_eq!(section.render("colors: false").unwrap(), "");
+
+    let partial = ribboncurls::Template::compile("a\n{{> footer}}")
+        .unwrap()
+        .with_strict_mode(ribboncurls::StrictMode::new());
+    let err = partial.render("{}").unwrap_err();
+    match &err {
+        ribboncurls::RibboncurlsError::MissingPartial { name, location } => {
+            assert_eq!(name, "footer");
+            assert_eq!((location.line, location.column), (2, 1));
+        }
+        _ => panic!("unexpected error: {err:?}"),
+    }
+
+    // Without strict mode missing things render nothing
+    let lenient =
+        ribboncurls::Template::compile("{{name}}{{#colors}}x{{/colors}}{{> footer}}").unwrap();
+    assert_eq!(lenient.render("{}").unwrap(), "");
+}
+
+#[test]
+fn escapers() {
+    let data = r#"name: "Tin\"ted's <b>&\\\n\u0001""#;
+    let render = |escaper: Box<dyn Fn(&str) -> String + Send + Sync>| {
+        ribboncurls::Template::compile("{{name}}|{{{name}}}")
+            .unwrap()
+            .with_escaper(escaper)
+            .render(data)
+            .unwrap()
+    };
+    let raw = "Tin\"ted's <b>&\\\n\u{1}";
+    let escape = |escaper: &dyn ribboncurls::Escaper| escaper.escape(raw);
+
+    assert_eq!(
+        escape(&ribboncurls::HtmlEscaper),
+        "Tin&quot;ted&#39;s &lt;b&gt;&amp;\\\n\u{1}"
+    );
+    assert_eq!(escape(&ribboncurls::NoEscaper), raw);
+    assert_eq!(
+        escape(&ribboncurls::JsonStringEscaper),
+        r#"Tin\"ted's <b>&\\\n\u0001"#
+    );
+    assert_eq!(
+        escape(&ribboncurls::ShellSingleQuoteEscaper),
+        "Tin\"ted'\\''s <b>&\\\n\u{1}"
+    );
+    assert_eq!(
+        escape(&ribboncurls::XmlEscaper),
+        "Tin&quot;ted&apos;s &lt;b&gt;&amp;\\\n\u{1}"
+    );
+
+    // Unescaped tags ignore the escaper
+    assert_eq!(
+        render(Box::new(|text: &str| text.to_uppercase())),
+        format!("{}|{raw}", raw.to_uppercase())
+    );
+}
+
+#[test]
+fn value_formatters() {
+    use ribboncurls::{ConfigFormatter, MappingFormat, MustacheFormatter, Template};
+
+    let template =
+        "{{on}}|{{off}}|{{none}}|{{int}}|{{float}}|{{big}}|{{{list}}}|{{{map}}}|{{tagged}}";
+    let data = r#"
+        on: true
+        off: false
+        none: null
+        int: 42
+        float: 0.125
+        big: 1500.0
+        list: [1, [a, false], {b: 2}]
+        map: {name: 'Tinted: dark', dark: true, sizes: [1, 2], empty: '', '1.5': 'null'}
+        tagged: !color '#181818'
+    "#;
+    let render = |formatter: ConfigFormatter| {
+        Template::compile(template)
+            .unwrap()
+            .with_formatter(formatter)
+            .render(data)
+            .unwrap()
+    };
+
+    assert_eq!(
+        Template::compile(template).unwrap().render(data).unwrap(),
+        "true|||42|0.125|1500.0|||"
+    );
+    assert_eq!(
+        Template::compile(template)
+            .unwrap()
+            .with_formatter(MustacheFormatter)
+            .render(data)
+            .unwrap(),
+        "true|||42|0.125|1500.0|||"
+    );
+    assert_eq!(
+        render(ConfigFormatter::new()),
+        r##"true|false||42|0.125|1500.0|1, a, false, {"b":2}|{"name":"Tinted: dark","dark":true,"sizes":[1,2],"empty":"","1.5":"null"}|#181818"##
+    );
+    assert_eq!(
+        render(
+            ConfigFormatter::new()
+                .booleans("yes", "no")
+                .null("nil")
+                .float_precision(1)
+                .separator(";")
+                .mappings(MappingFormat::Yaml)
+        ),
+        r##"yes|no|nil|42|0.1|1500.0|1;a;no;{b: 2}|{name: "Tinted: dark", dark: true, sizes: [1, 2], empty: "", "1.5": "null"}|#181818"##
+    );
+    assert_eq!(
+        render(
+            ConfigFormatter::new()
+                .float_exponent(true)
+                .mappings(MappingFormat::Empty)
+        ),
+        "true|false||42|1.25e-1|1.5e3|1, a, false, ||#181818"
+    );
+}
+
+#[test]
+fn json_and_toml_data_and_partials() {
+    use ribboncurls::DataFormat;
+
+    let template = ribboncurls::Template::compile("{{> title}} {{version}} {{released}}")
+        .unwrap()
+        .with_partials_as(r#"{"title": "{{package.name}}"}"#, DataFormat::Json)
+        .unwrap();
+    let toml = r#"
+version = 2
+released = 2024-05-01
+
+[package]
+name = "tinted"
+"#;
+    let json = r#"{"package": {"name": "tinted"}, "version": 2.5, "released": null}"#;
+
+    assert_eq!(
+        template.render_as(toml, DataFormat::Toml).unwrap(),
+        "tinted 2 2024-05-01"
+    );
+    assert_eq!(
+        template.render_as(json, DataFormat::Json).unwrap(),
+        "tinted 2.5 "
+    );
+    assert!(matches!(
+        template.render_as("version = ", DataFormat::Toml),
+        Err(ribboncurls::RibboncurlsError::TomlParseError(_))
+    ));
+
+    let template = ribboncurls::Template::compile("{{> title}}")
+        .unwrap()
+        .with_partials_as("title = \"{{name}}\"", DataFormat::Toml)
+        .unwrap();
+    assert_eq!(
+        template
+            .render_as(r#"{"name": "tinted"}"#, DataFormat::Json)
+            .unwrap(),
+        "tinted"
+    );
+}
+
+#[test]
+fn template_references() {
+    use ribboncurls::ReferenceKind;
+
+    let template = ribboncurls::Template::compile(
+        "{{scheme.name}}\n{{#palette}}\n  {{{base00}}}{{^dark}}{{&base01}}{{/dark}}\n{{/palette}}\n{{> header}}{{>*variant}}{{<layout}}{{$body}}{{author}}{{/body}}{{/layout}}",
+    )
+    .unwrap();
+    let references: Vec<_> = template
+        .references()
+        .into_iter()
+        .map(|reference| {
+            (
+                reference.kind,
+                reference.name,
+                reference.path.join("."),
+                reference.location.line,
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        references,
+        [
+            (
+                ReferenceKind::Variable,
+                "scheme.name".into(),
+                String::new(),
+                1
+            ),
+            (ReferenceKind::Section, "palette".into(), String::new(), 2),
+            (
+                ReferenceKind::RawVariable,
+                "base00".into(),
+                "palette".into(),
+                3
+            ),
+            (
+                ReferenceKind::InvertedSection,
+                "dark".into(),
+                "palette".into(),
+                3
+            ),
+            (
+                ReferenceKind::RawVariable,
+                "base01".into(),
+                "palette.dark".into(),
+                3
+            ),
+            (ReferenceKind::Partial, "header".into(), String::new(), 5),
+            (
+                ReferenceKind::DynamicPartial,
+                "variant".into(),
+                String::new(),
+                5
+            ),
+            (ReferenceKind::Parent, "layout".into(), String::new(), 5),
+            (ReferenceKind::Variable, "author".into(), String::new(), 5),
+        ]
+    );
+}
+
+#[test]
+fn template_and_partial_delimiters_are_independent() {
+    use ribboncurls::{Delimiters, Template};
+
+    let delimiters = Delimiters::new("<%", "%>").unwrap();
+    let template = Template::compile_with_delimiters(
+        "{{ .Release.Name }}: <% name %>\n<%> header%>",
+        &delimiters,
+    )
+    .unwrap()
+    .with_partials("header: '{{ name }} <%{name}%>'")
+    .unwrap();
+
+    // Partials start with the default delimiters until they are set
+    assert_eq!(
+        template.render("name: '<b>'").unwrap(),
+        "{{ .Release.Name }}: &lt;b&gt;\n&lt;b&gt; <%{name}%>"
+    );
+
+    let template = template.with_partial_delimiters("[[ ]]".parse().unwrap());
+    assert_eq!(
+        template.render("name: '<b>'").unwrap(),
+        "{{ .Release.Name }}: &lt;b&gt;\n{{ name }} <%{name}%>"
+    );
+
+    // Replacing the partials keeps their delimiters
+    let template = template
+        .with_partials("header: '[[&name]] [[=| |=]]|name|'")
+        .unwrap();
+    assert_eq!(
+        template.render("name: '<b>'").unwrap(),
+        "{{ .Release.Name }}: &lt;b&gt;\n<b> &lt;b&gt;"
+    );
+}
+
+#[test]
+fn deeply_nested_sections_compile_and_render() {
+    // Compiling used to copy every section's content and the tree below it, so memory grew much
+    // faster than the template. The deep recursion needs more stack than test threads get.
+    let depth = 2000;
+    let template = format!("{}x{}", "{{#a}}".repeat(depth), "{{/a}}".repeat(depth));
+    let rendered = std::thread::Builder::new()
+        .stack_size(256 << 20)
+        .spawn(move || {
+            let limits = ribboncurls::Limits::new().section_depth(depth);
+            ribboncurls::Template::compile_with_limits(&template, limits)
+                .unwrap()
+                .render("a: true")
+                .unwrap()
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    assert_eq!(rendered, "x");
+}
@@ -0,0 +1,192 @@
+#![allow(clippy::unwrap_used, clippy::needless_raw_string_hashes)]
+
+use ribboncurls::RibboncurlsError;
+
+/// Renders `template` with partials, expecting it to fail to parse
+fn parse_error(template: &str) -> RibboncurlsError {
+    let partials = r#"layout: "{{$title}}{{/title}}""#;
+
+    ribboncurls::render(template, "{}", Some(partials)).unwrap_err()
+}
+
+#[test]
+fn unclosed_sections() {
+    let cases = [
+        ("{{#a}}", "a", 1),
+        ("{{^a}}", "a", 1),
+        ("{{<layout}}", "layout", 1),
+        ("{{<layout}}{{$title}}", "title", 12),
+        ("{{#a}}{{/a}}{{#b}}", "b", 13),
+        ("{{#a}}{{#b}}{{/b}}", "a", 1),
+        ("{{#a}}\n  {{^b}}\n{{/b}}", "a", 1),
+        ("{{=<% %>=}}<%#a%>", "a", 12),
+    ];
+
+    for (template, expected_name, expected_column) in cases {
+        match parse_error(template) {
+            RibboncurlsError::UnclosedSection { name, location } => {
+                assert_eq!(name, expected_name, "{template}");
+                assert_eq!(location.column, expected_column, "{template}");
+            }
+            err => panic!("unexpected error for {template:?}: {err:?}"),
+        }
+    }
+}
+
+#[test]
+fn innermost_unclosed_section_is_reported() {
+    let err = parse_error("{{#a}}\n{{#b}}\n");
+
+    assert_eq!(
+        err.to_string(),
+        r#"section "b" is never closed, opened at line 2, column 1"#
+    );
+}
+
+#[test]
+fn unexpected_close_tags() {
+    let cases = [
+        ("{{/a}}", "a", 1),
+        ("text {{/ a }}", "a", 6),
+        ("{{#a}}{{/a}}{{/a}}", "a", 13),
+        ("{{^a}}{{/a}}\n{{/b}}", "b", 1),
+    ];
+
+    for (template, expected_name, expected_column) in cases {
+        match parse_error(template) {
+            RibboncurlsError::UnexpectedCloseTag { name, location } => {
+                assert_eq!(name, expected_name, "{template}");
+                assert_eq!(location.column, expected_column, "{template}");
+            }
+            err => panic!("unexpected error for {template:?}: {err:?}"),
+        }
+    }
+}
+
+#[test]
+fn mismatched_close_tags() {
+    let cases = [
+        ("{{#a}}{{/b}}", "a", "b"),
+        ("{{^a}}{{/b}}", "a", "b"),
+        ("{{#a}}{{#b}}{{/a}}{{/b}}", "b", "a"),
+        ("{{#a}}{{^b}}{{/a}}", "b", "a"),
+        ("{{<layout}}{{/title}}", "layout", "title"),
+        ("{{<layout}}{{$title}}{{/layout}}", "title", "layout"),
+        ("{{#a}}{{/A}}", "a", "A"),
+    ];
+
+    for (template, expected_open, expected_close) in cases {
+        match parse_error(template) {
+            RibboncurlsError::MismatchedSection {
+                expected, found, ..
+            } => {
+                assert_eq!(expected, expected_open, "{template}");
+                assert_eq!(found, expected_close, "{template}");
+            }
+            err => panic!("unexpected error for {template:?}: {err:?}"),
+        }
+    }
+}
+
+#[test]
+fn malformed_partials_are_rejected_when_rendered() {
+    let err = ribboncurls::render("{{>footer}}", "{}", Some("footer: '{{^a}}'")).unwrap_err();
+
+    match err {
+        RibboncurlsError::UnclosedSection { name, location } => {
+            assert_eq!(name, "a");
+            assert_eq!(location.partial.as_deref(), Some("footer"));
+        }
+        err => panic!("unexpected error: {err:?}"),
+    }
+}
+
+#[test]
+fn balanced_templates_still_render() {
+    let template = "{{#a}}{{^b}}{{#c}}x{{/c}}{{/b}}{{/a}}{{! {{/a}}";
+    let data = "{ a: true, b: false, c: true }";
+
+    assert_eq!(ribboncurls::render(template, data, None).unwrap(), "x");
+}
+
+#[test]
+fn empty_set_delimiters() {
+    for template in ["{{=}}", "{{= =}}", "{{==}}", "a{{=  =}}"] {
+        assert!(
+            matches!(
+                parse_error(template),
+                RibboncurlsError::MissingDelimiter { .. }
+            ),
+            "{template}"
+        );
+    }
+}
+
+#[test]
+fn bad_set_delimiters() {
+    let cases = [
+        ("{{=<% %> %%=}}", "expected 2 delimiters but found 3"),
+        ("{{=<%= %>=}}", "delimiter \"<%=\" contains \"=\""),
+        ("{{=<% =%>=}}", "delimiter \"=%>\" contains \"=\""),
+        ("{{=<% %>}}", "expected \"=\" at the end"),
+        ("é {{=«« »»=}}««=a b»»", "expected \"=\" at the end"),
+    ];
+
+    for (template, expected_message) in cases {
+        match parse_error(template) {
+            RibboncurlsError::BadDelimiter { message, .. } => {
+                assert_eq!(message, expected_message, "{template}");
+            }
+            err => panic!("unexpected error for {template:?}: {err:?}"),
+        }
+    }
+
+    // Columns count characters, so multi-byte delimiters before the tag count once each
+    let err = parse_error("é {{=«« »»=}}««=a b»»");
+    assert_eq!(err.location().unwrap().column, 14);
+}
+
+#[test]
+fn set_delimiters_are_separated_by_any_whitespace() {
+    let result = ribboncurls::render(
+        "{{=«  \t»=}}«name» {{name}} «={{\n}}=»{{name}}",
+        "name: a",
+        None,
+    );
+
+    assert_eq!(result.unwrap(), "a {{name}} a");
+}
+
+#[test]
+fn multibyte_delimiters_with_triple_mustaches() {
+    let result = ribboncurls::render("{{=« »=}}««name»» «name»", "name: '<b>'", None).unwrap();
+
+    assert_eq!(result, "<b> &lt;b&gt;");
+}
+
+#[test]
+fn invalid_starting_delimiters() {
+    use ribboncurls::Delimiters;
+
+    let cases = [
+        ("<% %> %%", "expected 2 delimiters but found 3"),
+        ("<%", "expected 2 delimiters but found 1"),
+        ("<%= %>", "delimiter \"<%=\" contains \"=\""),
+    ];
+
+    for (delimiters, expected_message) in cases {
+        match delimiters.parse::<Delimiters>() {
+            Err(RibboncurlsError::InvalidDelimiters { message, .. }) => {
+                assert_eq!(message, expected_message, "{delimiters}");
+            }
+            result => panic!("unexpected result for {delimiters:?}: {result:?}"),
+        }
+    }
+
+    assert!(Delimiters::new("", "%>").is_err());
+    assert!(Delimiters::new("<%", "% >").is_err());
+    assert_eq!(
+        Delimiters::new("«", "»").unwrap().to_string(),
+        "« »".to_string()
+    );
+}
@@ -0,0 +1,122 @@
+//! Property tests that feed arbitrary templates, data and partials to the library, which should
+//! return errors for bad input rather than panic
+
+use std::collections::HashMap;
+
+use proptest::prelude::*;
+use ribboncurls::{Limits, Template};
+
+/// Pieces of Mustache syntax, put together in any order so that most templates are close to valid
+const FRAGMENTS: &[&str] = &[
+    "{{",
+    "}}",
+    "{{{",
+    "}}}",
+    "{{#a}}",
+    "{{/a}}",
+    "{{^b}}",
+    "{{/b}}",
+    "{{#.}}",
+    "{{/.}}",
+    "{{>a}}",
+    "{{>b}}",
+    "{{>*name}}",
+    "{{<a}}",
+    "{{$c}}",
+    "{{/c}}",
+    "{{&a}}",
+    "{{!x}}",
+    "{{=<% %>=}}",
+    "<%",
+    "%>",
+    "<%={{ }}=%>",
+    "{{=}}",
+    "{{= =}}",
+    "{{=«» »=}}",
+    "«",
+    "»",
+    "{{.}}",
+    "{{a.b}}",
+    "{{name}}",
+    "{{ a | upper }}",
+    "{{ a | default:\"x\" }}",
+    "{{ a | rgb:r }}",
+    "{{ a | mix:\"fff\",2 }}",
+    "\n",
+    "\r\n",
+    "\r",
+    "  ",
+    "\t",
+    "a",
+    "é",
+    "=",
+    "|",
+    ":",
+    "\"",
+    "{",
+    "}",
+    "#",
+    "/",
+];
+
+const DATA: &[&str] = &[
+    "a: true",
+    "{a: [1, [2, 3], {b: x}], b: false}",
+    "{a: {b: c}, name: a, c: [[x], []]}",
+    "[a, [b, c]]",
+    "{name: '{{a}}', a: '#7cafc2'}",
+    "a: ~",
+];
+
+fn fragments(max: usize) -> impl Strategy<Value = String> {
+    prop::collection::vec(prop::sample::select(FRAGMENTS), 0..max).prop_map(|parts| parts.concat())
+}
+
+fn template() -> impl Strategy<Value = String> {
+    prop_oneof![fragments(40), any::<String>()]
+}
+
+fn data() -> impl Strategy<Value = String> {
+    prop_oneof![
+        prop::sample::select(DATA).prop_map(str::to_string),
+        any::<String>()
+    ]
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn render_never_panics(template in template(), data in data(), partial in fragments(20)) {
+        // Partials that include each other more than once grow exponentially without limits, so
+        // these don't include partials
+        let partial = partial.replace("{{>", "{{!").replace("{{<", "{{!");
+        let partials = HashMap::from([("a".to_string(), partial.clone()), ("b".to_string(), partial)]);
+        let value = serde_yaml::from_str::<serde_yaml::Value>(&data).ok();
+
+        let _ = ribboncurls::render(&template, &data, None);
+        // The data doubles as partials, which are rejected unless it is a mapping of strings
+        let _ = ribboncurls::render(&template, "{}", Some(&data));
+        let _ = ribboncurls::render_with(&template, &value, &partials);
+        let _ = ribboncurls::ast::parse(&template);
+    }
+
+    #[test]
+    fn compiled_templates_never_panic(
+        template in template(),
+        data in data(),
+        partial_a in fragments(20),
+        partial_b in fragments(20),
+    ) {
+        let Ok(template) = Template::compile(&template) else {
+            return Ok(());
+        };
+        let _ = template.references();
+
+        let partials = HashMap::from([("a".to_string(), partial_a), ("b".to_string(), partial_b)]);
+        let template = template
+            .with_partial_map(partials)
+            .with_limits(Limits::new().partial_depth(6).output_bytes(1 << 16).iterations(1 << 12));
+        let _ = template.render(&data);
+    }
+}